@@ -1,9 +1,13 @@
 pragma solidity ^0.5.0;
 
 import "@openzeppelin/contracts/token/ERC20/ERC20.sol";
+import "@openzeppelin/contracts/token/ERC20/ERC20Detailed.sol";
 
-contract Token is ERC20 {
-    constructor() public {
-        _mint(msg.sender, 1000000000000000000000000000);
+contract Token is ERC20, ERC20Detailed {
+    constructor(string memory name, string memory symbol, uint8 decimals, uint256 supply)
+        public
+        ERC20Detailed(name, symbol, decimals)
+    {
+        _mint(msg.sender, supply);
     }
 }
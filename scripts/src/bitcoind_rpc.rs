@@ -0,0 +1,733 @@
+use anyhow::Context;
+use lazy_static::lazy_static;
+use rust_bitcoin::{
+    hashes::{hmac, sha256, sha256d, Hash, HashEngine},
+    Address, Amount, SignedAmount,
+};
+use secp256k1::rand::{thread_rng, Rng};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+
+use crate::seed::Seed;
+
+const JSONRPC_VERSION: &str = "1.0";
+
+lazy_static! {
+    // `reqwest::Client` keeps a connection pool internally, sharing one instance means every
+    // `Client` re-uses the same connections to bitcoind
+    static ref HTTP_CLIENT: reqwest::Client = reqwest::Client::new();
+}
+
+/// The credentials of the RPC interface, bitcoind itself only learns the salted hash of the
+/// password through `-rpcauth`.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl Credentials {
+    /// Derives the password from the seed so that every environment has its own credentials and
+    /// the same seed yields the same env file.
+    pub fn from_seed(username: &str, seed: &Seed) -> Self {
+        Self {
+            username: username.to_owned(),
+            password: hex::encode(seed.derive("bitcoind/rpc_password")),
+        }
+    }
+
+    /// The value of bitcoind's `-rpcauth` argument, computed like `share/rpcauth/rpcauth.py`.
+    pub fn rpcauth(&self) -> String {
+        let mut salt = [0u8; 16];
+        thread_rng().fill(&mut salt);
+
+        self.rpcauth_with_salt(&hex::encode(salt))
+    }
+
+    fn rpcauth_with_salt(&self, salt: &str) -> String {
+        let mut engine = hmac::HmacEngine::<sha256::Hash>::new(salt.as_bytes());
+        engine.input(self.password.as_bytes());
+        let hmac = hmac::Hmac::<sha256::Hash>::from_engine(engine);
+
+        format!(
+            "{}:{}${}",
+            self.username,
+            salt,
+            hex::encode(hmac.into_inner())
+        )
+    }
+}
+
+/// A JSON-RPC client for a bitcoind node or one of its wallets.
+///
+/// Cloning a `Client` is cheap, all clones share the same connection pool.
+#[derive(Debug, Clone)]
+pub struct Client {
+    url: String,
+    username: String,
+    password: String,
+}
+
+impl Client {
+    pub fn new(
+        url: impl Into<String>,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        Self {
+            url: url.into(),
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    /// Returns a client for the wallet endpoint `/wallet/<wallet_name>` of the same node.
+    pub fn with_wallet(&self, wallet_name: &str) -> Self {
+        Self {
+            url: format!("{}/wallet/{}", self.url.trim_end_matches('/'), wallet_name),
+            username: self.username.clone(),
+            password: self.password.clone(),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub async fn send<T>(&self, request: &Request) -> anyhow::Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        let response = HTTP_CLIENT
+            .post(&self.url)
+            .basic_auth(&self.username, Some(&self.password))
+            .json(request)
+            .send()
+            .await
+            .with_context(|| format!("failed to send {} request", request.method))?
+            .json::<Response>()
+            .await
+            .with_context(|| format!("failed to deserialize {} response", request.method))?;
+
+        response.into_result()
+    }
+
+    /// Sends all requests in a single JSON-RPC batch.
+    ///
+    /// The results are returned in the order of the requests, the batch fails as a whole if any of
+    /// the requests failed.
+    pub async fn batch<T>(&self, requests: Vec<Request>) -> anyhow::Result<Vec<T>>
+    where
+        T: DeserializeOwned,
+    {
+        self.batch_results(requests).await?.into_iter().collect()
+    }
+
+    /// Sends all requests in a single JSON-RPC batch and returns the result of each request in
+    /// the order of the requests.
+    pub async fn batch_results<T>(
+        &self,
+        requests: Vec<Request>,
+    ) -> anyhow::Result<Vec<anyhow::Result<T>>>
+    where
+        T: DeserializeOwned,
+    {
+        // bitcoind does not guarantee the order of the responses, the index is used to match them
+        let requests = requests
+            .into_iter()
+            .enumerate()
+            .map(|(index, request)| Request {
+                id: index.to_string(),
+                ..request
+            })
+            .collect::<Vec<_>>();
+
+        let responses = HTTP_CLIENT
+            .post(&self.url)
+            .basic_auth(&self.username, Some(&self.password))
+            .json(&requests)
+            .send()
+            .await
+            .context("failed to send batch request")?
+            .json::<Vec<Response>>()
+            .await
+            .context("failed to deserialize batch response")?;
+
+        if responses.len() != requests.len() {
+            anyhow::bail!(
+                "expected {} responses to batch request but got {}",
+                requests.len(),
+                responses.len()
+            )
+        }
+
+        let mut ordered = std::iter::repeat_with(|| None)
+            .take(requests.len())
+            .collect::<Vec<_>>();
+        for response in responses {
+            let index = response
+                .id
+                .parse::<usize>()
+                .ok()
+                .filter(|index| *index < requests.len())
+                .with_context(|| format!("unexpected id {} in batch response", response.id))?;
+            ordered[index] = Some(response);
+        }
+
+        ordered
+            .into_iter()
+            .map(|response| {
+                response
+                    .context("missing response in batch response")
+                    .map(Response::into_result)
+            })
+            .collect()
+    }
+
+    pub async fn create_wallet(
+        &self,
+        wallet_name: &str,
+        disable_private_keys: bool,
+    ) -> anyhow::Result<CreateWalletResponse> {
+        self.send(&Request::create_wallet(wallet_name, disable_private_keys))
+            .await
+    }
+
+    /// Names of the wallets which are currently loaded.
+    pub async fn list_wallets(&self) -> anyhow::Result<Vec<String>> {
+        self.send(&Request::list_wallets()).await
+    }
+
+    pub async fn load_wallet(&self, wallet_name: &str) -> anyhow::Result<LoadWalletResponse> {
+        self.send(&Request::load_wallet(wallet_name)).await
+    }
+
+    /// Imports the descriptor into the wallet and rescans the chain.
+    pub async fn import_descriptor(
+        &self,
+        descriptor: &str,
+        range: (u32, u32),
+        watch_only: bool,
+    ) -> anyhow::Result<()> {
+        let results = self
+            .send::<Vec<ImportMultiResult>>(&Request::import_descriptor(
+                descriptor, range, watch_only,
+            ))
+            .await?;
+
+        for result in results {
+            if let Some(error) = result.error {
+                return Err(anyhow::Error::new(error));
+            }
+            if !result.success {
+                anyhow::bail!("failed to import descriptor {}", descriptor)
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_new_address(&self, address_format: &str) -> anyhow::Result<Address> {
+        self.send(&Request::get_new_address(address_format)).await
+    }
+
+    pub async fn generate_to_address(
+        &self,
+        number: u32,
+        address: &Address,
+    ) -> anyhow::Result<Vec<sha256d::Hash>> {
+        self.send(&Request::generate_to_address(number, address))
+            .await
+    }
+
+    pub async fn send_to_address(
+        &self,
+        address: &Address,
+        amount: Amount,
+    ) -> anyhow::Result<sha256d::Hash> {
+        self.send(&Request::send_to_address(address, amount)).await
+    }
+
+    pub async fn send_many(&self, amounts: &[(Address, Amount)]) -> anyhow::Result<sha256d::Hash> {
+        self.send(&Request::send_many(amounts)).await
+    }
+
+    /// Adds inputs and a change output to a transaction given as hex.
+    pub async fn fund_raw_transaction(
+        &self,
+        transaction: &str,
+    ) -> anyhow::Result<RawTransactionResponse> {
+        self.send(&Request::fund_raw_transaction(transaction)).await
+    }
+
+    pub async fn sign_raw_transaction_with_wallet(
+        &self,
+        transaction: &str,
+    ) -> anyhow::Result<RawTransactionResponse> {
+        self.send(&Request::sign_raw_transaction_with_wallet(transaction))
+            .await
+    }
+
+    pub async fn send_raw_transaction(&self, transaction: &str) -> anyhow::Result<sha256d::Hash> {
+        self.send(&Request::send_raw_transaction(transaction)).await
+    }
+
+    pub async fn get_descriptor_info(&self, descriptor: &str) -> anyhow::Result<DescriptorInfo> {
+        self.send(&Request::get_descriptor_info(descriptor)).await
+    }
+
+    /// Derives the addresses of a descriptor, `range` is inclusive and must only be given for
+    /// ranged descriptors.
+    pub async fn derive_addresses(
+        &self,
+        descriptor: &str,
+        range: Option<(u32, u32)>,
+    ) -> anyhow::Result<Vec<Address>> {
+        self.send(&Request::derive_addresses(descriptor, range))
+            .await
+    }
+
+    pub async fn get_blockchain_info(&self) -> anyhow::Result<BlockchainInfo> {
+        self.send(&Request::get_blockchain_info()).await
+    }
+
+    pub async fn get_mempool_info(&self) -> anyhow::Result<MempoolInfo> {
+        self.send(&Request::get_mempool_info()).await
+    }
+
+    /// Sets the fee rate of the wallet's transactions, `fee_rate` is per kvB.
+    pub async fn set_tx_fee(&self, fee_rate: Amount) -> anyhow::Result<bool> {
+        self.send(&Request::set_tx_fee(fee_rate)).await
+    }
+
+    pub async fn get_balance(&self) -> anyhow::Result<Amount> {
+        let balance = self.send::<f64>(&Request::get_balance()).await?;
+
+        Amount::from_btc(balance).with_context(|| format!("invalid balance {}", balance))
+    }
+
+    pub async fn get_transaction(&self, txid: &sha256d::Hash) -> anyhow::Result<Transaction> {
+        self.send(&Request::get_transaction(txid)).await
+    }
+
+    pub async fn get_block_hash(&self, height: u64) -> anyhow::Result<sha256d::Hash> {
+        self.send(&Request::get_block_hash(height)).await
+    }
+
+    pub async fn get_chain_tips(&self) -> anyhow::Result<Vec<ChainTip>> {
+        self.send(&Request::get_chain_tips()).await
+    }
+
+    pub async fn invalidate_block(&self, block_hash: &sha256d::Hash) -> anyhow::Result<()> {
+        self.send(&Request::invalidate_block(block_hash)).await
+    }
+
+    pub async fn reconsider_block(&self, block_hash: &sha256d::Hash) -> anyhow::Result<()> {
+        self.send(&Request::reconsider_block(block_hash)).await
+    }
+
+    /// Enables or disables all P2P network activity of the node, returns the new state.
+    pub async fn set_network_active(&self, active: bool) -> anyhow::Result<bool> {
+        self.send(&Request::set_network_active(active)).await
+    }
+
+    /// `command` is one of `add`, `remove` or `onetry`.
+    pub async fn add_node(&self, node: &str, command: &str) -> anyhow::Result<()> {
+        self.send(&Request::add_node(node, command)).await
+    }
+
+    /// Sets the node's clock to the given UNIX timestamp, `0` goes back to the system clock.
+    pub async fn set_mock_time(&self, timestamp: u64) -> anyhow::Result<()> {
+        self.send(&Request::set_mock_time(timestamp)).await
+    }
+}
+
+/// A JSON-RPC request, the id defaults to the name of the method.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Request {
+    jsonrpc: String,
+    id: String,
+    method: String,
+    params: serde_json::Value,
+}
+
+impl Request {
+    pub fn new(method: &str, params: serde_json::Value) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_owned(),
+            id: method.to_owned(),
+            method: method.to_owned(),
+            params,
+        }
+    }
+
+    pub fn create_wallet(wallet_name: &str, disable_private_keys: bool) -> Self {
+        Self::new(
+            "createwallet",
+            serde_json::json!([wallet_name, disable_private_keys]),
+        )
+    }
+
+    pub fn list_wallets() -> Self {
+        Self::new("listwallets", serde_json::json!([]))
+    }
+
+    pub fn load_wallet(wallet_name: &str) -> Self {
+        Self::new("loadwallet", serde_json::json!([wallet_name]))
+    }
+
+    pub fn import_descriptor(descriptor: &str, range: (u32, u32), watch_only: bool) -> Self {
+        Self::new(
+            "importmulti",
+            serde_json::json!([
+                [{
+                    "desc": descriptor,
+                    "range": [range.0, range.1],
+                    "timestamp": 0,
+                    "watchonly": watch_only,
+                }],
+                { "rescan": true }
+            ]),
+        )
+    }
+
+    pub fn get_new_address(address_format: &str) -> Self {
+        Self::new("getnewaddress", serde_json::json!(["", address_format]))
+    }
+
+    pub fn generate_to_address(number: u32, address: &Address) -> Self {
+        Self::new("generatetoaddress", serde_json::json!([number, address]))
+    }
+
+    pub fn send_to_address(address: &Address, amount: Amount) -> Self {
+        Self::new(
+            "sendtoaddress",
+            serde_json::json!([address, amount.as_btc().to_string()]),
+        )
+    }
+
+    pub fn send_many(amounts: &[(Address, Amount)]) -> Self {
+        let amounts = amounts
+            .iter()
+            .map(|(address, amount)| {
+                (
+                    address.to_string(),
+                    serde_json::Value::String(amount.as_btc().to_string()),
+                )
+            })
+            .collect::<serde_json::Map<_, _>>();
+
+        Self::new("sendmany", serde_json::json!(["", amounts]))
+    }
+
+    pub fn fund_raw_transaction(transaction: &str) -> Self {
+        Self::new("fundrawtransaction", serde_json::json!([transaction]))
+    }
+
+    pub fn sign_raw_transaction_with_wallet(transaction: &str) -> Self {
+        Self::new(
+            "signrawtransactionwithwallet",
+            serde_json::json!([transaction]),
+        )
+    }
+
+    pub fn send_raw_transaction(transaction: &str) -> Self {
+        Self::new("sendrawtransaction", serde_json::json!([transaction]))
+    }
+
+    pub fn get_descriptor_info(descriptor: &str) -> Self {
+        Self::new("getdescriptorinfo", serde_json::json!([descriptor]))
+    }
+
+    pub fn derive_addresses(descriptor: &str, range: Option<(u32, u32)>) -> Self {
+        let params = match range {
+            Some((begin, end)) => serde_json::json!([descriptor, [begin, end]]),
+            None => serde_json::json!([descriptor]),
+        };
+
+        Self::new("deriveaddresses", params)
+    }
+
+    pub fn get_blockchain_info() -> Self {
+        Self::new("getblockchaininfo", serde_json::json!([]))
+    }
+
+    pub fn get_mempool_info() -> Self {
+        Self::new("getmempoolinfo", serde_json::json!([]))
+    }
+
+    pub fn set_tx_fee(fee_rate: Amount) -> Self {
+        Self::new(
+            "settxfee",
+            serde_json::json!([fee_rate.as_btc().to_string()]),
+        )
+    }
+
+    pub fn get_balance() -> Self {
+        Self::new("getbalance", serde_json::json!([]))
+    }
+
+    pub fn get_transaction(txid: &sha256d::Hash) -> Self {
+        Self::new("gettransaction", serde_json::json!([txid.to_string()]))
+    }
+
+    pub fn set_network_active(active: bool) -> Self {
+        Self::new("setnetworkactive", serde_json::json!([active]))
+    }
+
+    pub fn add_node(node: &str, command: &str) -> Self {
+        Self::new("addnode", serde_json::json!([node, command]))
+    }
+
+    pub fn get_block_hash(height: u64) -> Self {
+        Self::new("getblockhash", serde_json::json!([height]))
+    }
+
+    pub fn get_chain_tips() -> Self {
+        Self::new("getchaintips", serde_json::json!([]))
+    }
+
+    pub fn invalidate_block(block_hash: &sha256d::Hash) -> Self {
+        Self::new(
+            "invalidateblock",
+            serde_json::json!([block_hash.to_string()]),
+        )
+    }
+
+    pub fn reconsider_block(block_hash: &sha256d::Hash) -> Self {
+        Self::new(
+            "reconsiderblock",
+            serde_json::json!([block_hash.to_string()]),
+        )
+    }
+
+    pub fn set_mock_time(timestamp: u64) -> Self {
+        Self::new("setmocktime", serde_json::json!([timestamp]))
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Response {
+    #[serde(default)]
+    result: serde_json::Value,
+    error: Option<JsonRpcError>,
+    id: String,
+}
+
+impl Response {
+    fn into_result<T>(self) -> anyhow::Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        if let Some(error) = self.error {
+            return Err(anyhow::Error::new(error));
+        }
+
+        serde_json::from_value(self.result)
+            .with_context(|| format!("unexpected result in response to {}", self.id))
+    }
+}
+
+#[derive(Debug, serde::Deserialize, thiserror::Error)]
+#[error("JSON-RPC request failed with code {code}: {message}")]
+pub struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct CreateWalletResponse {
+    pub name: String,
+    pub warning: String,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct LoadWalletResponse {
+    pub name: String,
+    pub warning: String,
+}
+
+/// The result of `fundrawtransaction` and `signrawtransactionwithwallet`, `complete` is only
+/// returned by the latter.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct RawTransactionResponse {
+    pub hex: String,
+    #[serde(default)]
+    pub complete: bool,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct BlockchainInfo {
+    pub chain: String,
+    pub blocks: u64,
+    pub headers: u64,
+    #[serde(rename = "bestblockhash")]
+    pub best_block_hash: sha256d::Hash,
+    #[serde(rename = "mediantime")]
+    pub median_time: u64,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ChainTip {
+    pub height: u64,
+    pub hash: sha256d::Hash,
+    /// Length of the branch connecting the tip to the main chain, `0` for the active tip.
+    #[serde(rename = "branchlen")]
+    pub branch_len: u64,
+    /// One of `active`, `invalid`, `headers-only`, `valid-headers` or `valid-fork`.
+    pub status: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ImportMultiResult {
+    success: bool,
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct DescriptorInfo {
+    /// The descriptor in canonical form, without private keys.
+    pub descriptor: String,
+    pub checksum: String,
+    #[serde(rename = "isrange")]
+    pub is_range: bool,
+    #[serde(rename = "issolvable")]
+    pub is_solvable: bool,
+    #[serde(rename = "hasprivatekeys")]
+    pub has_private_keys: bool,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct MempoolInfo {
+    /// Number of transactions in the mempool.
+    pub size: u64,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Transaction {
+    pub txid: sha256d::Hash,
+    #[serde(deserialize_with = "signed_amount_from_btc")]
+    pub amount: SignedAmount,
+    /// Negative if the transaction conflicts with the active chain.
+    pub confirmations: i64,
+    #[serde(rename = "blockhash")]
+    pub block_hash: Option<sha256d::Hash>,
+    pub hex: String,
+}
+
+fn signed_amount_from_btc<'de, D>(deserializer: D) -> Result<SignedAmount, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let btc = f64::deserialize(deserializer)?;
+
+    SignedAmount::from_btc(btc).map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn generate_to_address_request_does_serialize() {
+        let expected = r#"{"jsonrpc":"1.0","id":"generatetoaddress","method":"generatetoaddress","params":[101,"2MubReUTptB6isbuFmsRiN3BPHaeHpiAjQM"]}"#;
+
+        let number = 101;
+        let address = Address::from_str("2MubReUTptB6isbuFmsRiN3BPHaeHpiAjQM").unwrap();
+
+        let request = Request::generate_to_address(number, &address);
+        let json = serde_json::to_string(&request).unwrap();
+
+        assert_eq!(json, expected)
+    }
+
+    #[test]
+    fn new_address_request_does_serialize() {
+        let expected = r#"{"jsonrpc":"1.0","id":"getnewaddress","method":"getnewaddress","params":["","bech32"]}"#;
+        let format = "bech32";
+
+        let request = Request::get_new_address(format);
+        let json = serde_json::to_string(&request).unwrap();
+
+        assert_eq!(json, expected)
+    }
+
+    #[test]
+    fn fund_request_does_serialize() {
+        let expected = r#"{"jsonrpc":"1.0","id":"sendtoaddress","method":"sendtoaddress","params":["2MubReUTptB6isbuFmsRiN3BPHaeHpiAjQM","1"]}"#;
+        let address = Address::from_str("2MubReUTptB6isbuFmsRiN3BPHaeHpiAjQM").unwrap();
+
+        let request = Request::send_to_address(&address, Amount::ONE_BTC);
+        let json = serde_json::to_string(&request).unwrap();
+
+        assert_eq!(json, expected)
+    }
+
+    #[test]
+    fn send_many_request_does_serialize() {
+        let expected = r#"{"jsonrpc":"1.0","id":"sendmany","method":"sendmany","params":["",{"2MubReUTptB6isbuFmsRiN3BPHaeHpiAjQM":"0.5"}]}"#;
+        let address = Address::from_str("2MubReUTptB6isbuFmsRiN3BPHaeHpiAjQM").unwrap();
+
+        let request = Request::send_many(&[(address, Amount::from_sat(50_000_000))]);
+        let json = serde_json::to_string(&request).unwrap();
+
+        assert_eq!(json, expected)
+    }
+
+    #[test]
+    fn error_response_does_not_yield_result() {
+        let json = r#"{"result":null,"error":{"code":-4,"message":"Insufficient funds"},"id":"sendtoaddress"}"#;
+
+        let response = serde_json::from_str::<Response>(json).unwrap();
+
+        assert!(response.into_result::<sha256d::Hash>().is_err())
+    }
+
+    #[test]
+    fn null_result_deserializes_to_unit() {
+        let json = r#"{"result":null,"error":null,"id":"invalidateblock"}"#;
+
+        let response = serde_json::from_str::<Response>(json).unwrap();
+
+        assert!(response.into_result::<()>().is_ok())
+    }
+
+    #[test]
+    fn rpcauth_matches_rpcauth_py() {
+        let credentials = Credentials {
+            username: "bitcoin".to_owned(),
+            password: "t68ej4UX2pB0cLlGwSwHFBLKxXYgomkXyFyxuBmm2U8=".to_owned(),
+        };
+
+        assert_eq!(
+            credentials.rpcauth_with_salt("1c0e8f3de84926c04115e7da7e501346"),
+            "bitcoin:1c0e8f3de84926c04115e7da7e501346$a48f42ad32741dd1755649c8b98663b3ccbebeb75f196389f9a5c8a96b72edb3"
+        );
+    }
+
+    #[test]
+    fn credentials_are_derived_from_the_seed() {
+        let seed = Seed::new("42".to_owned());
+
+        assert_eq!(
+            Credentials::from_seed("bitcoin", &seed),
+            Credentials::from_seed("bitcoin", &seed)
+        );
+        assert_ne!(
+            Credentials::from_seed("bitcoin", &seed),
+            Credentials::from_seed("bitcoin", &Seed::new("43".to_owned()))
+        );
+    }
+
+    #[test]
+    fn chain_tips_do_deserialize() {
+        let json = r#"[{"height":110,"hash":"0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206","branchlen":2,"status":"invalid"}]"#;
+
+        let tips = serde_json::from_str::<Vec<ChainTip>>(json).unwrap();
+
+        assert_eq!(tips[0].height, 110);
+        assert_eq!(tips[0].branch_len, 2);
+        assert_eq!(tips[0].status, "invalid");
+    }
+}
@@ -1,30 +1,1069 @@
-use rust_bitcoin::Address as BitcoinAddress;
+use crate::{bitcoind_rpc, env_vars};
+use anyhow::Context;
+use rust_bitcoin::{util::amount::Denomination, Address as BitcoinAddress, Amount};
 use serde_derive::Deserialize;
-use std::path::Path;
+use std::{
+    convert::TryFrom,
+    io,
+    path::{Path, PathBuf},
+};
+use web3::types::U256;
 
 pub const FILE_NAME: &str = "ComitScripts.toml";
 
-#[derive(Debug, Deserialize)]
+/// 10 BTC
+pub const DEFAULT_FUNDING_SATS: u64 = 1_000_000_000;
+/// The dust limit of p2pkh outputs, the highest of all standard output types.
+pub const DUST_SATS: u64 = 546;
+
+pub const ETHER_DECIMALS: usize = 18;
+pub const ERC20_DECIMALS: u8 = 18;
+/// 10^77 is the highest power of ten that fits into a `uint256`.
+const MAX_TOKEN_DECIMALS: u8 = 77;
+
+/// 1000 ether
+pub const DEFAULT_ETHER_FUNDING_WEI: u128 = 1_000_000_000_000_000_000_000;
+/// 100000 tokens
+pub const DEFAULT_ERC20_FUNDING: u128 = 100_000_000_000_000_000_000_000;
+/// 1000000000 tokens
+pub const DEFAULT_ERC20_SUPPLY: u128 = 1_000_000_000_000_000_000_000_000_000;
+/// Gas limit of contract deployments unless a contract configures its own
+pub const DEPLOY_GAS_LIMIT: u64 = 10_000_000;
+/// Highest fee rate of the fee market in sat/vB, bitcoind rejects higher ones with its default
+/// `-maxtxfee`.
+pub const MAX_FEE_RATE: u64 = 10_000;
+
+#[derive(Debug, Default, Deserialize)]
 pub struct Config {
+    /// Seed to derive all keys from, a random seed is used if absent.
+    pub seed: Option<String>,
     pub bitcoin: Option<Bitcoin>,
     pub ethereum: Option<Ethereum>,
+    pub faucet: Option<Faucet>,
+    /// Confirmations every funding transaction needs before the environment is ready, `1` if
+    /// absent.
+    pub funding_confirmations: Option<u32>,
 }
 
 impl Config {
-    pub fn from_file(path: &Path) -> anyhow::Result<Config> {
-        let toml_string = std::fs::read_to_string(path)?;
-        Ok(toml::from_str(toml_string.as_str())?)
+    /// Returns `None` if there is no config file at `path`.
+    pub fn from_file(path: &Path) -> anyhow::Result<Option<Config>> {
+        let toml_string = match std::fs::read_to_string(path) {
+            Ok(toml_string) => toml_string,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(e).with_context(|| format!("failed to read file {}", path.display()))
+            }
+        };
+        let config = toml::from_str(toml_string.as_str())
+            .with_context(|| format!("invalid config file {}", path.display()))?;
+
+        Ok(Some(config))
+    }
+
+    pub fn funding_confirmations(&self) -> u32 {
+        self.funding_confirmations.unwrap_or(1)
     }
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Default, Deserialize)]
 pub struct Bitcoin {
-    pub addresses_to_fund: Vec<BitcoinAddress>,
+    #[serde(default)]
+    pub addresses_to_fund: Vec<BitcoinFunding>,
+    #[serde(default)]
+    pub descriptors_to_fund: Vec<DescriptorFunding>,
+    #[serde(default)]
+    pub mining: Mining,
+    #[serde(default)]
+    pub actor_wallets: ActorWallets,
+    /// Address type of all actors which are not listed in `actor_address_types`.
+    #[serde(default)]
+    pub address_type: AddressType,
+    /// Address type per actor, the first entry is used for the first actor and so on.
+    #[serde(default)]
+    pub actor_address_types: Vec<AddressType>,
+    /// Outputs per actor, the first entry is used for the first actor and so on. Actors which are
+    /// not listed get a single output of 10 BTC.
+    #[serde(default)]
+    pub actor_utxos: Vec<UtxoSet>,
+    /// Starts a second bitcoind peered with the first one to simulate partitions and reorgs.
+    #[serde(default)]
+    pub second_node: bool,
+    /// The node the cnds connect to, `0` for the first and `1` for the second node.
+    #[serde(default)]
+    pub cnd_node: u32,
+    #[serde(default)]
+    pub fee_market: FeeMarket,
+    /// Starts an lnd per actor if present.
+    pub lightning: Option<Lightning>,
+    /// Credentials of bitcoind's RPC interface, a random password is generated if absent.
+    pub rpc_credentials: Option<bitcoind_rpc::Credentials>,
+    /// Starts electrs which serves the Electrum protocol and the Esplora REST API.
+    #[serde(default)]
+    pub electrs: bool,
+    /// Funds and mines through an already running bitcoind instead of starting a container.
+    pub external: Option<ExternalBitcoind>,
+    /// Appended to the default arguments of bitcoind, e.g. `["-maxmempool=50"]`.
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+    /// Replace the default arguments of bitcoind with the same option name, e.g.
+    /// `["-fallbackfee=0.001", "-notxindex"]`.
+    #[serde(default)]
+    pub override_args: Vec<String>,
+}
+
+/// A bitcoind on regtest which is not managed by comit-scripts, e.g.
+/// `{ rpc_url = "http://127.0.0.1:18443", user = "bitcoin", password = "..." }`.
+///
+/// A `localhost` URL is reached through `host.docker.internal` from the cnd containers.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ExternalBitcoind {
+    pub rpc_url: String,
+    pub user: String,
+    pub password: String,
+}
+
+impl Bitcoin {
+    pub fn address_type(&self, actor: usize) -> AddressType {
+        self.actor_address_types
+            .get(actor)
+            .copied()
+            .unwrap_or(self.address_type)
+    }
+
+    pub fn utxo_set(&self, actor: usize) -> UtxoSet {
+        self.actor_utxos.get(actor).copied().unwrap_or_default()
+    }
+}
+
+/// The script type of the actor accounts, each comes with the derivation path of its standard.
+/// p2tr is not supported since the bundled bitcoind does not know taproot.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AddressType {
+    /// BIP44: m/44'/1'/0'
+    P2pkh,
+    /// BIP49: m/49'/1'/0'
+    P2shP2wpkh,
+    /// BIP84: m/84'/1'/0'
+    P2wpkh,
+}
+
+impl Default for AddressType {
+    fn default() -> Self {
+        AddressType::P2wpkh
+    }
+}
+
+/// How the accounts of the actors are loaded into bitcoind wallets.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActorWallets {
+    /// The wallets hold the private keys and can spend.
+    PrivateKeys,
+    /// The wallets only hold the public keys.
+    WatchOnly,
+    /// No wallets are created for the actors.
+    None,
+}
+
+impl Default for ActorWallets {
+    fn default() -> Self {
+        ActorWallets::PrivateKeys
+    }
 }
 
+/// Either a plain address which is funded with the default amount or a table with an explicit
+/// amount, e.g. `{ address = "...", btc = "0.5" }`.
+///
+/// The table takes the fields of a `UtxoSet` to fund the address with many outputs, e.g.
+/// `{ address = "...", utxos = 50, utxo_amounts = "random(0.0001..0.5)" }`.
 #[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "BitcoinFundingEntry")]
+pub struct BitcoinFunding {
+    pub address: BitcoinAddress,
+    /// Split evenly across the outputs unless the `utxo_set` has its own amounts.
+    pub btc: Amount,
+    pub utxo_set: UtxoSet,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BitcoinFundingEntry {
+    Address(BitcoinAddress),
+    WithAmount {
+        address: BitcoinAddress,
+        btc: Option<String>,
+        utxos: Option<u32>,
+        utxo_amounts: Option<String>,
+        dust: Option<u32>,
+    },
+}
+
+impl TryFrom<BitcoinFundingEntry> for BitcoinFunding {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: BitcoinFundingEntry) -> anyhow::Result<Self> {
+        match entry {
+            BitcoinFundingEntry::Address(address) => Ok(BitcoinFunding {
+                address,
+                btc: Amount::from_sat(DEFAULT_FUNDING_SATS),
+                utxo_set: UtxoSet::default(),
+            }),
+            BitcoinFundingEntry::WithAmount {
+                address,
+                btc,
+                utxos,
+                utxo_amounts,
+                dust,
+            } => {
+                if btc.is_some() && utxo_amounts.is_some() {
+                    anyhow::bail!("either btc or utxo_amounts can be given for {}", address)
+                }
+
+                let btc = match btc {
+                    Some(btc) => Amount::from_str_in(&btc, Denomination::Bitcoin)
+                        .with_context(|| format!("{} is not a valid bitcoin amount", btc))?,
+                    None => Amount::from_sat(DEFAULT_FUNDING_SATS),
+                };
+                let utxo_set = UtxoSet::try_from(UtxoSetEntry {
+                    utxos,
+                    utxo_amounts,
+                    dust,
+                })?;
+
+                Ok(BitcoinFunding {
+                    address,
+                    btc,
+                    utxo_set,
+                })
+            }
+        }
+    }
+}
+
+/// Splits the funding of an actor or address into many outputs so that coin selection, dust
+/// handling and consolidation have something to work on, e.g.
+/// `{ utxos = 50, utxo_amounts = "random(0.0001..0.5)", dust = 5 }`.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "UtxoSetEntry")]
+pub struct UtxoSet {
+    /// Number of outputs, `1` if absent.
+    pub utxos: u32,
+    /// Amount of every output, the funding is split evenly if absent.
+    pub utxo_amounts: Option<UtxoAmounts>,
+    /// Number of additional outputs right at the dust limit.
+    pub dust: u32,
+}
+
+impl Default for UtxoSet {
+    fn default() -> Self {
+        UtxoSet {
+            utxos: 1,
+            utxo_amounts: None,
+            dust: 0,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct UtxoSetEntry {
+    utxos: Option<u32>,
+    utxo_amounts: Option<String>,
+    dust: Option<u32>,
+}
+
+impl TryFrom<UtxoSetEntry> for UtxoSet {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: UtxoSetEntry) -> anyhow::Result<Self> {
+        let utxos = entry.utxos.unwrap_or(1);
+        if utxos == 0 {
+            anyhow::bail!("utxos must be at least 1")
+        }
+
+        Ok(UtxoSet {
+            utxos,
+            utxo_amounts: entry
+                .utxo_amounts
+                .map(|amounts| amounts.parse())
+                .transpose()?,
+            dust: entry.dust.unwrap_or(0),
+        })
+    }
+}
+
+/// The amount of every output of a `UtxoSet`, either fixed like `"0.01"` or drawn uniformly from
+/// a range like `"random(0.0001..0.5)"`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UtxoAmounts {
+    Fixed(Amount),
+    Random { min: Amount, max: Amount },
+}
+
+impl std::str::FromStr for UtxoAmounts {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let parse = |btc: &str| {
+            Amount::from_str_in(btc.trim(), Denomination::Bitcoin)
+                .with_context(|| format!("{} is not a valid bitcoin amount", btc))
+        };
+
+        let s = s.trim();
+        let amounts = match s
+            .strip_prefix("random(")
+            .and_then(|range| range.strip_suffix(")"))
+        {
+            Some(range) => {
+                let mut bounds = range.splitn(2, "..");
+                let (min, max) = match (bounds.next(), bounds.next()) {
+                    (Some(min), Some(max)) => (parse(min)?, parse(max)?),
+                    _ => anyhow::bail!("{} is not a range like random(0.0001..0.5)", s),
+                };
+                if min > max {
+                    anyhow::bail!("the lower bound of {} exceeds the upper bound", s)
+                }
+
+                UtxoAmounts::Random { min, max }
+            }
+            None => UtxoAmounts::Fixed(parse(s)?),
+        };
+
+        let smallest = match amounts {
+            UtxoAmounts::Fixed(amount) => amount,
+            UtxoAmounts::Random { min, .. } => min,
+        };
+        if smallest < Amount::from_sat(DUST_SATS) {
+            anyhow::bail!(
+                "{} allows outputs below the dust limit of {} satoshis, use dust for those",
+                s,
+                DUST_SATS
+            )
+        }
+
+        Ok(amounts)
+    }
+}
+
+/// Funds the first `count` addresses of an output descriptor with `btc` each, e.g.
+/// `{ descriptor = "wpkh(tpub.../0/*)", count = 20, btc = "0.1" }`.
+///
+/// Instead of a descriptor an `xpub` can be given which is funded as `wpkh(<xpub>/0/*)`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "DescriptorFundingEntry")]
+pub struct DescriptorFunding {
+    pub descriptor: String,
+    pub count: u32,
+    pub btc: Amount,
+}
+
+#[derive(Deserialize)]
+struct DescriptorFundingEntry {
+    descriptor: Option<String>,
+    xpub: Option<String>,
+    count: u32,
+    btc: String,
+}
+
+impl TryFrom<DescriptorFundingEntry> for DescriptorFunding {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: DescriptorFundingEntry) -> anyhow::Result<Self> {
+        let descriptor = match (entry.descriptor, entry.xpub) {
+            (Some(descriptor), None) => descriptor,
+            (None, Some(xpub)) => format!("wpkh({}/0/*)", xpub),
+            _ => anyhow::bail!("either a descriptor or an xpub has to be given"),
+        };
+
+        Ok(DescriptorFunding {
+            descriptor,
+            count: entry.count,
+            btc: Amount::from_str_in(&entry.btc, Denomination::Bitcoin)
+                .with_context(|| format!("{} is not a valid bitcoin amount", entry.btc))?,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(try_from = "MiningEntry")]
+pub struct Mining {
+    /// Number of seconds between two blocks in `interval` mode.
+    pub block_interval: u64,
+    pub mode: MiningMode,
+    /// Number of seconds to wait after transactions arrived before mining in `on_mempool` mode.
+    pub mempool_delay: u64,
+    /// Minimum number of transactions in the mempool to mine a block in `on_mempool` mode.
+    pub min_mempool_transactions: u64,
+}
+
+impl Default for Mining {
+    fn default() -> Self {
+        Mining {
+            block_interval: 1,
+            mode: MiningMode::Interval,
+            mempool_delay: 0,
+            min_mempool_transactions: 1,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct MiningEntry {
+    block_interval: Option<u64>,
+    mode: Option<MiningMode>,
+    mempool_delay: Option<u64>,
+    min_mempool_transactions: Option<u64>,
+}
+
+impl TryFrom<MiningEntry> for Mining {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: MiningEntry) -> anyhow::Result<Self> {
+        let default = Mining::default();
+
+        let mining = Mining {
+            block_interval: entry.block_interval.unwrap_or(default.block_interval),
+            mode: entry.mode.unwrap_or(default.mode),
+            mempool_delay: entry.mempool_delay.unwrap_or(default.mempool_delay),
+            min_mempool_transactions: entry
+                .min_mempool_transactions
+                .unwrap_or(default.min_mempool_transactions),
+        };
+
+        if mining.block_interval == 0 {
+            anyhow::bail!("block_interval must be at least one second")
+        }
+
+        Ok(mining)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MiningMode {
+    /// A block is mined every `block_interval` seconds.
+    Interval,
+    /// Blocks are only mined on demand with `comit-scripts mine btc <blocks>`.
+    Manual,
+    /// A block is mined as soon as there are transactions in the mempool.
+    OnMempool,
+}
+
+/// Background transactions at a spread of fee rates so that `estimatesmartfee` has data, e.g.
+/// `{ enabled = true, congestion = 2, min_fee_rate = 1, max_fee_rate = 100 }`.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(try_from = "FeeMarketEntry")]
+pub struct FeeMarket {
+    pub enabled: bool,
+    /// Number of full blocks worth of transactions kept in the mempool.
+    pub congestion: u32,
+    /// Lowest fee rate in sat/vB.
+    pub min_fee_rate: u64,
+    /// Highest fee rate in sat/vB.
+    pub max_fee_rate: u64,
+    /// Maximum weight of mined blocks, only applied if the fee market is enabled. Smaller blocks
+    /// need fewer transactions to be congested.
+    pub block_max_weight: u64,
+}
+
+impl Default for FeeMarket {
+    fn default() -> Self {
+        FeeMarket {
+            enabled: false,
+            congestion: 2,
+            min_fee_rate: 1,
+            max_fee_rate: 100,
+            block_max_weight: 100_000,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct FeeMarketEntry {
+    enabled: Option<bool>,
+    congestion: Option<u32>,
+    min_fee_rate: Option<u64>,
+    max_fee_rate: Option<u64>,
+    block_max_weight: Option<u64>,
+}
+
+impl TryFrom<FeeMarketEntry> for FeeMarket {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: FeeMarketEntry) -> anyhow::Result<Self> {
+        let default = FeeMarket::default();
+
+        let fee_market = FeeMarket {
+            enabled: entry.enabled.unwrap_or(default.enabled),
+            congestion: entry.congestion.unwrap_or(default.congestion),
+            min_fee_rate: entry.min_fee_rate.unwrap_or(default.min_fee_rate),
+            max_fee_rate: entry.max_fee_rate.unwrap_or(default.max_fee_rate),
+            block_max_weight: entry.block_max_weight.unwrap_or(default.block_max_weight),
+        };
+
+        if fee_market.max_fee_rate > MAX_FEE_RATE {
+            anyhow::bail!("max_fee_rate must not exceed {} sat/vB", MAX_FEE_RATE)
+        }
+        if fee_market.min_fee_rate > fee_market.max_fee_rate {
+            anyhow::bail!("min_fee_rate must not exceed max_fee_rate")
+        }
+
+        Ok(fee_market)
+    }
+}
+
+/// An lnd per actor backed by the environment's bitcoind, e.g.
+/// `{ funding = "1", channel_capacity = "0.1", push_amount = "0.05" }`.
+///
+/// The first actor's lnd opens a channel with `channel_capacity` to the second actor's lnd and
+/// pushes `push_amount` to it.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(try_from = "LightningEntry")]
+pub struct Lightning {
+    /// On-chain funds of each lnd.
+    pub funding: Amount,
+    pub channel_capacity: Amount,
+    pub push_amount: Amount,
+}
+
+impl Default for Lightning {
+    fn default() -> Self {
+        Lightning {
+            funding: Amount::ONE_BTC,
+            channel_capacity: Amount::from_sat(10_000_000),
+            push_amount: Amount::from_sat(0),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct LightningEntry {
+    funding: Option<String>,
+    channel_capacity: Option<String>,
+    push_amount: Option<String>,
+}
+
+impl TryFrom<LightningEntry> for Lightning {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: LightningEntry) -> anyhow::Result<Self> {
+        let default = Lightning::default();
+        let parse = |amount: Option<String>, default: Amount| {
+            amount
+                .map(|btc| {
+                    Amount::from_str_in(&btc, Denomination::Bitcoin)
+                        .with_context(|| format!("{} is not a valid bitcoin amount", btc))
+                })
+                .transpose()
+                .map(|amount| amount.unwrap_or(default))
+        };
+
+        let lightning = Lightning {
+            funding: parse(entry.funding, default.funding)?,
+            channel_capacity: parse(entry.channel_capacity, default.channel_capacity)?,
+            push_amount: parse(entry.push_amount, default.push_amount)?,
+        };
+
+        if lightning.channel_capacity > lightning.funding {
+            anyhow::bail!("channel_capacity must not exceed the funding of the lnd")
+        }
+        if lightning.push_amount > lightning.channel_capacity {
+            anyhow::bail!("push_amount must not exceed the channel_capacity")
+        }
+
+        Ok(lightning)
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(try_from = "EthereumEntry")]
 pub struct Ethereum {
-    pub addresses_to_fund: Vec<web3::types::Address>,
+    #[serde(default)]
+    pub addresses_to_fund: Vec<EthereumFunding>,
+    #[serde(default)]
+    pub node: EthereumNode,
+    /// ERC20 tokens deployed next to the default token, e.g. `[[ethereum.tokens]]`.
+    #[serde(default)]
+    pub tokens: Vec<Token>,
+    /// Compiled contracts deployed after the tokens, e.g. `[[ethereum.contracts]]`.
+    #[serde(default)]
+    pub contracts: Vec<Contract>,
+    /// Funds and deploys through an already running node instead of starting a container.
+    pub external: Option<ExternalEthereum>,
+    /// Appended to the default arguments of the node, e.g. `["--cache=256"]`.
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+    /// Replace the default arguments of the node with the same option name, e.g.
+    /// `["--verbosity=2"]`.
+    #[serde(default)]
+    pub override_args: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct EthereumEntry {
+    #[serde(default)]
+    addresses_to_fund: Vec<EthereumFunding>,
+    #[serde(default)]
+    node: EthereumNode,
+    #[serde(default)]
+    tokens: Vec<Token>,
+    #[serde(default)]
+    contracts: Vec<Contract>,
+    external: Option<ExternalEthereum>,
+    #[serde(default)]
+    extra_args: Vec<String>,
+    #[serde(default)]
+    override_args: Vec<String>,
+}
+
+impl TryFrom<EthereumEntry> for Ethereum {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: EthereumEntry) -> anyhow::Result<Self> {
+        // checked before anything is deployed so that start-env does not stop half way
+        for (index, token) in entry.tokens.iter().enumerate() {
+            if entry.tokens[..index]
+                .iter()
+                .any(|other| other.symbol.eq_ignore_ascii_case(&token.symbol))
+            {
+                anyhow::bail!("token symbol {} is used more than once", token.symbol)
+            }
+        }
+        for (index, contract) in entry.contracts.iter().enumerate() {
+            if entry.contracts[..index]
+                .iter()
+                .any(|other| other.env == contract.env)
+            {
+                anyhow::bail!(
+                    "env variable {} is used by more than one contract",
+                    contract.env
+                )
+            }
+            if entry
+                .tokens
+                .iter()
+                .any(|token| env_vars::token_contract_address(&token.symbol) == contract.env)
+            {
+                anyhow::bail!(
+                    "env variable {} is already used by a token contract",
+                    contract.env
+                )
+            }
+        }
+
+        Ok(Ethereum {
+            addresses_to_fund: entry.addresses_to_fund,
+            node: entry.node,
+            tokens: entry.tokens,
+            contracts: entry.contracts,
+            external: entry.external,
+            extra_args: entry.extra_args,
+            override_args: entry.override_args,
+        })
+    }
+}
+
+/// An Ethereum node which is not managed by comit-scripts, e.g.
+/// `{ http_url = "http://127.0.0.1:8545" }`.
+///
+/// Its coinbase account has to be unlockable with an empty password and hold enough ether to fund
+/// all accounts, like the dev account of geth `--dev`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ExternalEthereum {
+    pub http_url: String,
+}
+
+/// The Ethereum client started in the `ethereum` container.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EthereumNode {
+    /// geth in `--dev` mode.
+    Geth,
+    /// ganache-cli, which supports `evm_increaseTime` and thus `advance-time`.
+    Ganache,
+}
+
+impl Default for EthereumNode {
+    fn default() -> Self {
+        EthereumNode::Geth
+    }
+}
+
+/// Either a plain address which is funded with the default amount of every asset or a table which
+/// only funds the assets it lists, e.g. `{ address = "...", eth = "2", erc20 = "1500" }`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "EthereumFundingEntry")]
+pub struct EthereumFunding {
+    pub address: web3::types::Address,
+    /// Amount in wei
+    pub eth: Option<U256>,
+    /// Amount in the smallest unit of the token
+    pub erc20: Option<U256>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EthereumFundingEntry {
+    Address(web3::types::Address),
+    WithAmounts {
+        address: web3::types::Address,
+        eth: Option<String>,
+        erc20: Option<String>,
+    },
+}
+
+impl TryFrom<EthereumFundingEntry> for EthereumFunding {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: EthereumFundingEntry) -> anyhow::Result<Self> {
+        match entry {
+            EthereumFundingEntry::Address(address) => Ok(EthereumFunding {
+                address,
+                eth: Some(U256::from(DEFAULT_ETHER_FUNDING_WEI)),
+                erc20: Some(U256::from(DEFAULT_ERC20_FUNDING)),
+            }),
+            EthereumFundingEntry::WithAmounts {
+                address,
+                eth,
+                erc20,
+            } => Ok(EthereumFunding {
+                address,
+                eth: eth
+                    .map(|eth| parse_decimal(&eth, ETHER_DECIMALS))
+                    .transpose()?,
+                erc20: erc20
+                    .map(|erc20| parse_decimal(&erc20, usize::from(ERC20_DECIMALS)))
+                    .transpose()?,
+            }),
+        }
+    }
+}
+
+/// An ERC20 token deployed next to the default token, e.g.
+/// `{ name = "USD Coin", symbol = "USDC", decimals = 6, supply = "1000000000", allocation = "1000" }`.
+///
+/// Amounts are in whole tokens. Every actor gets `allocation` unless `actor_allocations` lists an
+/// amount for it, the first entry is used for the first actor and so on.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "TokenEntry")]
+pub struct Token {
+    pub name: String,
+    /// Also names the env variable of the contract address, see
+    /// `env_vars::token_contract_address`.
+    pub symbol: String,
+    pub decimals: u8,
+    /// Minted to the deployment account, in the smallest unit of the token.
+    pub supply: U256,
+    /// In the smallest unit of the token.
+    pub allocation: U256,
+    /// In the smallest unit of the token.
+    pub actor_allocations: Vec<U256>,
+}
+
+impl Token {
+    /// The default token which is exposed as `ERC20_CONTRACT_ADDRESS`.
+    pub fn erc20() -> Self {
+        Token {
+            name: "ERC20 Token".to_owned(),
+            symbol: "ERC20".to_owned(),
+            decimals: ERC20_DECIMALS,
+            supply: U256::from(DEFAULT_ERC20_SUPPLY),
+            allocation: U256::from(DEFAULT_ERC20_FUNDING),
+            actor_allocations: Vec::new(),
+        }
+    }
+
+    pub fn allocation(&self, actor: usize) -> U256 {
+        self.actor_allocations
+            .get(actor)
+            .copied()
+            .unwrap_or(self.allocation)
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenEntry {
+    name: String,
+    symbol: String,
+    decimals: Option<u8>,
+    supply: Option<String>,
+    allocation: Option<String>,
+    #[serde(default)]
+    actor_allocations: Vec<String>,
+}
+
+impl TryFrom<TokenEntry> for Token {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: TokenEntry) -> anyhow::Result<Self> {
+        if entry.symbol.is_empty() || !entry.symbol.chars().all(|c| c.is_ascii_alphanumeric()) {
+            anyhow::bail!(
+                "token symbol {} has to be alphanumeric, it is part of an env variable",
+                entry.symbol
+            )
+        }
+
+        let decimals = entry.decimals.unwrap_or(ERC20_DECIMALS);
+        if decimals > MAX_TOKEN_DECIMALS {
+            anyhow::bail!(
+                "token {} has more than {} decimals",
+                entry.symbol,
+                MAX_TOKEN_DECIMALS
+            )
+        }
+
+        let parse = |amount: &str| {
+            parse_decimal(amount, usize::from(decimals))
+                .with_context(|| format!("invalid amount of token {}", entry.symbol))
+        };
+        // the default token supply and allocation are whole tokens as well
+        let whole_tokens = |smallest_unit: u128| {
+            (U256::from(smallest_unit) / U256::exp10(usize::from(ERC20_DECIMALS)))
+                .checked_mul(U256::exp10(usize::from(decimals)))
+                .with_context(|| {
+                    format!(
+                        "the default supply of token {} overflows, set supply and allocation",
+                        entry.symbol
+                    )
+                })
+        };
+
+        let supply = match &entry.supply {
+            Some(supply) => parse(supply)?,
+            None => whole_tokens(DEFAULT_ERC20_SUPPLY)?,
+        };
+        let allocation = match &entry.allocation {
+            Some(allocation) => parse(allocation)?,
+            None => whole_tokens(DEFAULT_ERC20_FUNDING)?,
+        };
+        let actor_allocations = entry
+            .actor_allocations
+            .iter()
+            .map(|allocation| parse(allocation))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        if std::iter::once(&allocation)
+            .chain(&actor_allocations)
+            .any(|allocation| *allocation > supply)
+        {
+            anyhow::bail!(
+                "the allocations of token {} must not exceed its supply",
+                entry.symbol
+            )
+        }
+
+        Ok(Token {
+            name: entry.name,
+            symbol: entry.symbol,
+            decimals,
+            supply,
+            allocation,
+            actor_allocations,
+        })
+    }
+}
+
+/// A compiled contract deployed at startup, e.g.
+/// `{ env = "REGISTRY_ADDRESS", artifact = "build/contracts/Registry.json", constructor_args = "0x..." }`.
+///
+/// `artifact` is a Truffle or Hardhat artifact, alternatively `bytecode` names a file with the hex
+/// bytecode and `abi` one with the ABI JSON. Paths are relative to `ComitScripts.toml`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "ContractEntry")]
+pub struct Contract {
+    /// The env variable the contract address is written to.
+    pub env: String,
+    pub source: ContractSource,
+    /// ABI encoded, appended to the bytecode.
+    pub constructor_args: Vec<u8>,
+    pub gas_limit: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ContractSource {
+    Artifact(PathBuf),
+    Files { bytecode: PathBuf, abi: PathBuf },
+}
+
+#[derive(Deserialize)]
+struct ContractEntry {
+    env: String,
+    artifact: Option<PathBuf>,
+    bytecode: Option<PathBuf>,
+    abi: Option<PathBuf>,
+    constructor_args: Option<String>,
+    gas_limit: Option<u64>,
+}
+
+impl TryFrom<ContractEntry> for Contract {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: ContractEntry) -> anyhow::Result<Self> {
+        let is_env_name = entry
+            .env
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+            && entry
+                .env
+                .chars()
+                .next()
+                .map_or(false, |c| !c.is_ascii_digit());
+        if !is_env_name {
+            anyhow::bail!(
+                "{} is not a valid env variable, use uppercase letters, digits and underscores",
+                entry.env
+            )
+        }
+        if env_vars::is_written(&entry.env) {
+            anyhow::bail!(
+                "env variable {} is already written by comit-scripts",
+                entry.env
+            )
+        }
+
+        let source = match (entry.artifact, entry.bytecode, entry.abi) {
+            (Some(artifact), None, None) => ContractSource::Artifact(artifact),
+            (None, Some(bytecode), Some(abi)) => ContractSource::Files { bytecode, abi },
+            _ => anyhow::bail!(
+                "either an artifact or bytecode and abi have to be given for {}",
+                entry.env
+            ),
+        };
+
+        let constructor_args = match entry.constructor_args {
+            Some(args) => hex::decode(args.trim_start_matches("0x"))
+                .with_context(|| format!("constructor_args of {} are not valid hex", entry.env))?,
+            None => Vec::new(),
+        };
+
+        Ok(Contract {
+            env: entry.env,
+            source,
+            constructor_args,
+            gas_limit: entry.gas_limit.unwrap_or(DEPLOY_GAS_LIMIT),
+        })
+    }
+}
+
+/// The local faucet started alongside the environment, e.g.
+/// `{ max_btc = "1", max_eth = "10", max_erc20 = "1000", requests_per_minute = 10 }`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "FaucetEntry")]
+pub struct Faucet {
+    pub enabled: bool,
+    /// Maximum amount of a single bitcoin request.
+    pub max_btc: Amount,
+    /// Maximum amount of a single ether request in wei.
+    pub max_eth: U256,
+    /// Maximum amount of a single token request in the smallest unit of the token.
+    pub max_erc20: U256,
+    /// Maximum number of requests over all assets within a minute.
+    pub requests_per_minute: u32,
+    /// Maximum amount of bitcoin paid out over the lifetime of the faucet.
+    pub max_total_btc: Amount,
+    /// Maximum amount of ether paid out over the lifetime of the faucet in wei.
+    pub max_total_eth: U256,
+    /// Maximum amount of tokens paid out over the lifetime of the faucet in the smallest unit of
+    /// the token.
+    pub max_total_erc20: U256,
+}
+
+impl Default for Faucet {
+    fn default() -> Self {
+        Faucet {
+            enabled: true,
+            max_btc: Amount::from_sat(DEFAULT_FUNDING_SATS),
+            max_eth: U256::from(DEFAULT_ETHER_FUNDING_WEI),
+            max_erc20: U256::from(DEFAULT_ERC20_FUNDING),
+            requests_per_minute: 60,
+            max_total_btc: Amount::from_sat(DEFAULT_FUNDING_SATS * 10),
+            max_total_eth: U256::from(DEFAULT_ETHER_FUNDING_WEI * 10),
+            max_total_erc20: U256::from(DEFAULT_ERC20_FUNDING * 10),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct FaucetEntry {
+    enabled: Option<bool>,
+    max_btc: Option<String>,
+    max_eth: Option<String>,
+    max_erc20: Option<String>,
+    requests_per_minute: Option<u32>,
+    max_total_btc: Option<String>,
+    max_total_eth: Option<String>,
+    max_total_erc20: Option<String>,
+}
+
+impl TryFrom<FaucetEntry> for Faucet {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: FaucetEntry) -> anyhow::Result<Self> {
+        let default = Faucet::default();
+
+        Ok(Faucet {
+            enabled: entry.enabled.unwrap_or(default.enabled),
+            max_btc: entry
+                .max_btc
+                .map(|btc| parse_bitcoin(&btc))
+                .transpose()?
+                .unwrap_or(default.max_btc),
+            max_eth: entry
+                .max_eth
+                .map(|eth| parse_decimal(&eth, ETHER_DECIMALS))
+                .transpose()?
+                .unwrap_or(default.max_eth),
+            max_erc20: entry
+                .max_erc20
+                .map(|erc20| parse_decimal(&erc20, usize::from(ERC20_DECIMALS)))
+                .transpose()?
+                .unwrap_or(default.max_erc20),
+            requests_per_minute: entry
+                .requests_per_minute
+                .unwrap_or(default.requests_per_minute),
+            max_total_btc: entry
+                .max_total_btc
+                .map(|btc| parse_bitcoin(&btc))
+                .transpose()?
+                .unwrap_or(default.max_total_btc),
+            max_total_eth: entry
+                .max_total_eth
+                .map(|eth| parse_decimal(&eth, ETHER_DECIMALS))
+                .transpose()?
+                .unwrap_or(default.max_total_eth),
+            max_total_erc20: entry
+                .max_total_erc20
+                .map(|erc20| parse_decimal(&erc20, usize::from(ERC20_DECIMALS)))
+                .transpose()?
+                .unwrap_or(default.max_total_erc20),
+        })
+    }
+}
+
+fn parse_bitcoin(btc: &str) -> anyhow::Result<Amount> {
+    Amount::from_str_in(btc, Denomination::Bitcoin)
+        .with_context(|| format!("{} is not a valid bitcoin amount", btc))
+}
+
+/// Parses a decimal amount such as "1.5" into an integer of the smallest unit.
+pub fn parse_decimal(amount: &str, decimals: usize) -> anyhow::Result<U256> {
+    let (integer, fraction) = match amount.find('.') {
+        Some(index) => (&amount[..index], &amount[index + 1..]),
+        None => (amount, ""),
+    };
+
+    if fraction.len() > decimals {
+        anyhow::bail!("{} has more than {} decimals", amount, decimals)
+    }
+
+    let digits = format!("{}{:0<width$}", integer, fraction, width = decimals);
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        anyhow::bail!("{} is not a valid amount", amount)
+    }
+
+    U256::from_dec_str(&digits)
+        .map_err(|e| anyhow::anyhow!("{} is not a valid amount: {:?}", amount, e))
 }
 
 #[cfg(test)]
@@ -43,4 +1082,537 @@ mod tests {
 
         let _config: Config = toml::from_str(toml).expect("failed to deserialise config toml");
     }
+
+    #[test]
+    fn test_seed_deserialize() {
+        let toml = r#"
+          seed = "42"
+        "#;
+
+        let config: Config = toml::from_str(toml).expect("failed to deserialise config toml");
+
+        assert_eq!(config.seed, Some("42".to_owned()));
+    }
+
+    #[test]
+    fn test_actor_wallets_deserialize() {
+        let toml = r#"
+          [bitcoin]
+          actor_wallets = "watch_only"
+        "#;
+
+        let config: Config = toml::from_str(toml).expect("failed to deserialise config toml");
+
+        assert_eq!(
+            config.bitcoin.unwrap().actor_wallets,
+            ActorWallets::WatchOnly
+        );
+    }
+
+    #[test]
+    fn test_address_types_deserialize() {
+        let toml = r#"
+          [bitcoin]
+          address_type = "p2pkh"
+          actor_address_types = ["p2sh-p2wpkh"]
+        "#;
+
+        let config: Config = toml::from_str(toml).expect("failed to deserialise config toml");
+        let bitcoin = config.bitcoin.unwrap();
+
+        assert_eq!(bitcoin.address_type(0), AddressType::P2shP2wpkh);
+        assert_eq!(bitcoin.address_type(1), AddressType::P2pkh);
+    }
+
+    #[test]
+    fn test_p2tr_address_type_is_rejected() {
+        let toml = r#"
+          [bitcoin]
+          address_type = "p2tr"
+        "#;
+
+        assert!(toml::from_str::<Config>(toml).is_err());
+    }
+
+    #[test]
+    fn test_second_node_deserialize() {
+        let toml = r#"
+          [bitcoin]
+          second_node = true
+          cnd_node = 1
+        "#;
+
+        let config: Config = toml::from_str(toml).expect("failed to deserialise config toml");
+        let bitcoin = config.bitcoin.unwrap();
+
+        assert!(bitcoin.second_node);
+        assert_eq!(bitcoin.cnd_node, 1);
+    }
+
+    #[test]
+    fn test_mining_config_deserialize() {
+        let toml = r#"
+          [bitcoin.mining]
+          block_interval = 10
+          mode = "manual"
+        "#;
+
+        let config: Config = toml::from_str(toml).expect("failed to deserialise config toml");
+        let mining = config.bitcoin.unwrap().mining;
+
+        assert_eq!(mining.block_interval, 10);
+        assert_eq!(mining.mode, MiningMode::Manual);
+    }
+
+    #[test]
+    fn test_on_mempool_mining_config_deserialize() {
+        let toml = r#"
+          [bitcoin.mining]
+          mode = "on_mempool"
+          min_mempool_transactions = 2
+        "#;
+
+        let config: Config = toml::from_str(toml).expect("failed to deserialise config toml");
+        let mining = config.bitcoin.unwrap().mining;
+
+        assert_eq!(mining.mode, MiningMode::OnMempool);
+        assert_eq!(mining.mempool_delay, 0);
+        assert_eq!(mining.min_mempool_transactions, 2);
+    }
+
+    #[test]
+    fn missing_config_file_is_not_an_error() {
+        let path = Path::new("does/not/exist/ComitScripts.toml");
+
+        assert!(Config::from_file(path).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_zero_block_interval_is_rejected() {
+        let toml = r#"
+          [bitcoin.mining]
+          block_interval = 0
+        "#;
+
+        assert!(toml::from_str::<Config>(toml).is_err());
+    }
+
+    #[test]
+    fn test_plain_addresses_get_default_amounts() {
+        let toml = r#"
+          [bitcoin]
+          addresses_to_fund = ["1GiYYLU6amEX5NnCeS1fuPG5WcVZfRmbiV"]
+
+          [ethereum]
+          addresses_to_fund = ["0x89205A3A3b2A69De6Dbf7f01ED13B2108B2c43e7"]
+        "#;
+
+        let config: Config = toml::from_str(toml).expect("failed to deserialise config toml");
+
+        let bitcoin = config.bitcoin.unwrap().addresses_to_fund;
+        assert_eq!(bitcoin[0].btc, Amount::from_sat(1_000_000_000));
+
+        let ethereum = config.ethereum.unwrap().addresses_to_fund;
+        assert!(ethereum[0].eth.is_some());
+        assert!(ethereum[0].erc20.is_some());
+    }
+
+    #[test]
+    fn test_funding_amounts_deserialize() {
+        let toml = r#"
+          [bitcoin]
+          addresses_to_fund = [
+            { address = "1GiYYLU6amEX5NnCeS1fuPG5WcVZfRmbiV", btc = "0.5" },
+          ]
+
+          [ethereum]
+          addresses_to_fund = [
+            { address = "0x89205A3A3b2A69De6Dbf7f01ED13B2108B2c43e7", eth = "2" },
+          ]
+        "#;
+
+        let config: Config = toml::from_str(toml).expect("failed to deserialise config toml");
+
+        let bitcoin = config.bitcoin.unwrap().addresses_to_fund;
+        assert_eq!(bitcoin[0].btc, Amount::from_sat(50_000_000));
+
+        let ethereum = config.ethereum.unwrap().addresses_to_fund;
+        assert_eq!(
+            ethereum[0].eth,
+            Some(U256::from(2_000_000_000_000_000_000u128))
+        );
+        assert_eq!(ethereum[0].erc20, None);
+    }
+
+    #[test]
+    fn test_descriptors_to_fund_deserialize() {
+        let toml = r#"
+          [bitcoin]
+          descriptors_to_fund = [
+            { descriptor = "wpkh(tpubD6NzVbkrYhZ4WaWSyoBvQwbpLkojyoTZPRsgXELWz3Popb3qkjcJyJUGLnL4qHHoQvao8ESaAstxYSnhyswJ76uZPStJRJCTKvosUCJZL5B/0/*)", count = 20, btc = "0.1" },
+            { xpub = "tpubD6NzVbkrYhZ4WaWSyoBvQwbpLkojyoTZPRsgXELWz3Popb3qkjcJyJUGLnL4qHHoQvao8ESaAstxYSnhyswJ76uZPStJRJCTKvosUCJZL5B", count = 5, btc = "1" },
+          ]
+        "#;
+
+        let config: Config = toml::from_str(toml).expect("failed to deserialise config toml");
+        let descriptors = config.bitcoin.unwrap().descriptors_to_fund;
+
+        assert_eq!(descriptors[0].count, 20);
+        assert_eq!(descriptors[0].btc, Amount::from_sat(10_000_000));
+        assert_eq!(descriptors[1].descriptor, "wpkh(tpubD6NzVbkrYhZ4WaWSyoBvQwbpLkojyoTZPRsgXELWz3Popb3qkjcJyJUGLnL4qHHoQvao8ESaAstxYSnhyswJ76uZPStJRJCTKvosUCJZL5B/0/*)");
+    }
+
+    #[test]
+    fn faucet_limits_default_to_funding_amounts() {
+        let toml = r#"
+            [faucet]
+            max_btc = "0.5"
+            requests_per_minute = 5
+        "#;
+
+        let config = toml::from_str::<Config>(toml).unwrap();
+        let faucet = config.faucet.unwrap();
+
+        assert!(faucet.enabled);
+        assert_eq!(faucet.max_btc, Amount::from_sat(50_000_000));
+        assert_eq!(faucet.max_eth, U256::from(DEFAULT_ETHER_FUNDING_WEI));
+        assert_eq!(faucet.requests_per_minute, 5);
+        assert_eq!(
+            faucet.max_total_btc,
+            Amount::from_sat(DEFAULT_FUNDING_SATS * 10)
+        );
+    }
+
+    #[test]
+    fn faucet_total_limits_are_parsed() {
+        let toml = r#"
+            [faucet]
+            max_total_btc = "2"
+            max_total_eth = "1.5"
+            max_total_erc20 = "100"
+        "#;
+
+        let config = toml::from_str::<Config>(toml).unwrap();
+        let faucet = config.faucet.unwrap();
+
+        assert_eq!(faucet.max_total_btc, Amount::from_sat(200_000_000));
+        assert_eq!(
+            faucet.max_total_eth,
+            U256::from(1_500_000_000_000_000_000u128)
+        );
+        assert_eq!(
+            faucet.max_total_erc20,
+            U256::from(100_000_000_000_000_000_000u128)
+        );
+    }
+
+    #[test]
+    fn fee_market_is_disabled_by_default() {
+        let toml = r#"
+            [bitcoin.fee_market]
+            congestion = 3
+        "#;
+
+        let config = toml::from_str::<Config>(toml).unwrap();
+        let fee_market = config.bitcoin.unwrap().fee_market;
+
+        assert!(!fee_market.enabled);
+        assert_eq!(fee_market.congestion, 3);
+        assert_eq!(fee_market.max_fee_rate, 100);
+    }
+
+    #[test]
+    fn fee_rates_are_bounded() {
+        let too_high = r#"
+            [bitcoin.fee_market]
+            max_fee_rate = 100000
+        "#;
+        let min_above_max = r#"
+            [bitcoin.fee_market]
+            min_fee_rate = 50
+            max_fee_rate = 10
+        "#;
+
+        assert!(toml::from_str::<Config>(too_high).is_err());
+        assert!(toml::from_str::<Config>(min_above_max).is_err());
+    }
+
+    #[test]
+    fn lightning_channel_must_fit_funding() {
+        let toml = r#"
+            [bitcoin.lightning]
+            funding = "0.5"
+            channel_capacity = "0.1"
+        "#;
+
+        let config = toml::from_str::<Config>(toml).unwrap();
+        let lightning = config.bitcoin.unwrap().lightning.unwrap();
+
+        assert_eq!(lightning.funding, Amount::from_sat(50_000_000));
+        assert_eq!(lightning.push_amount, Amount::from_sat(0));
+
+        let toml = r#"
+            [bitcoin.lightning]
+            funding = "0.05"
+        "#;
+
+        assert!(toml::from_str::<Config>(toml).is_err());
+    }
+
+    #[test]
+    fn ethereum_node_defaults_to_geth() {
+        let config = toml::from_str::<Config>("[ethereum]").unwrap();
+        assert_eq!(config.ethereum.unwrap().node, EthereumNode::Geth);
+
+        let toml = r#"
+            [ethereum]
+            node = "ganache"
+        "#;
+
+        let config = toml::from_str::<Config>(toml).unwrap();
+        assert_eq!(config.ethereum.unwrap().node, EthereumNode::Ganache);
+    }
+
+    #[test]
+    fn funding_confirmations_default_to_one() {
+        assert_eq!(Config::default().funding_confirmations(), 1);
+
+        let config = toml::from_str::<Config>("funding_confirmations = 6").unwrap();
+        assert_eq!(config.funding_confirmations(), 6);
+    }
+
+    #[test]
+    fn external_nodes_do_deserialize() {
+        let toml = r#"
+            [bitcoin]
+            external = { rpc_url = "http://127.0.0.1:18443", user = "bitcoin", password = "secret" }
+
+            [ethereum]
+            external = { http_url = "http://127.0.0.1:8545" }
+        "#;
+
+        let config = toml::from_str::<Config>(toml).unwrap();
+
+        assert_eq!(
+            config.bitcoin.unwrap().external.unwrap().rpc_url,
+            "http://127.0.0.1:18443"
+        );
+        assert_eq!(
+            config.ethereum.unwrap().external.unwrap().http_url,
+            "http://127.0.0.1:8545"
+        );
+    }
+
+    #[test]
+    fn utxo_sets_do_deserialize() {
+        let toml = r#"
+            [bitcoin]
+            actor_utxos = [{ utxos = 50, utxo_amounts = "random(0.0001..0.5)", dust = 5 }]
+            addresses_to_fund = [
+                { address = "2MubReUTptB6isbuFmsRiN3BPHaeHpiAjQM", btc = "1", utxos = 20 },
+                { address = "2MubReUTptB6isbuFmsRiN3BPHaeHpiAjQM", utxos = 5, utxo_amounts = "0.01" },
+            ]
+        "#;
+
+        let config = toml::from_str::<Config>(toml).unwrap();
+        let bitcoin = config.bitcoin.unwrap();
+
+        assert_eq!(
+            bitcoin.utxo_set(0),
+            UtxoSet {
+                utxos: 50,
+                utxo_amounts: Some(UtxoAmounts::Random {
+                    min: Amount::from_sat(10_000),
+                    max: Amount::from_sat(50_000_000),
+                }),
+                dust: 5,
+            }
+        );
+        assert_eq!(bitcoin.utxo_set(1), UtxoSet::default());
+        assert_eq!(bitcoin.addresses_to_fund[0].btc, Amount::ONE_BTC);
+        assert_eq!(bitcoin.addresses_to_fund[0].utxo_set.utxos, 20);
+        assert_eq!(
+            bitcoin.addresses_to_fund[1].utxo_set.utxo_amounts,
+            Some(UtxoAmounts::Fixed(Amount::from_sat(1_000_000)))
+        );
+    }
+
+    #[test]
+    fn invalid_utxo_amounts_are_rejected() {
+        assert!("random(0.5..0.0001)".parse::<UtxoAmounts>().is_err());
+        assert!("random(0.1)".parse::<UtxoAmounts>().is_err());
+        assert!("0.000001".parse::<UtxoAmounts>().is_err());
+        assert!("random(0..1)".parse::<UtxoAmounts>().is_err());
+    }
+
+    #[test]
+    fn tokens_do_deserialize() {
+        let toml = r#"
+            [[ethereum.tokens]]
+            name = "USD Coin"
+            symbol = "USDC"
+            decimals = 6
+            supply = "1000000"
+            allocation = "1000.5"
+            actor_allocations = ["10"]
+
+            [[ethereum.tokens]]
+            name = "Dai"
+            symbol = "DAI"
+        "#;
+
+        let config = toml::from_str::<Config>(toml).unwrap();
+        let tokens = config.ethereum.unwrap().tokens;
+
+        assert_eq!(tokens[0].decimals, 6);
+        assert_eq!(tokens[0].supply, U256::from(1_000_000_000_000u64));
+        assert_eq!(tokens[0].allocation(0), U256::from(10_000_000u64));
+        assert_eq!(tokens[0].allocation(1), U256::from(1_000_500_000u64));
+        assert_eq!(tokens[1].decimals, 18);
+        assert_eq!(tokens[1].supply, Token::erc20().supply);
+        assert_eq!(tokens[1].allocation(0), Token::erc20().allocation);
+    }
+
+    #[test]
+    fn invalid_tokens_are_rejected() {
+        let invalid_symbol = r#"
+            [[ethereum.tokens]]
+            name = "USD Coin"
+            symbol = "US-D"
+        "#;
+        let too_many_decimals = r#"
+            [[ethereum.tokens]]
+            name = "USD Coin"
+            symbol = "USDC"
+            decimals = 6
+            supply = "1.0000001"
+        "#;
+        let allocation_exceeds_supply = r#"
+            [[ethereum.tokens]]
+            name = "USD Coin"
+            symbol = "USDC"
+            supply = "10"
+            allocation = "11"
+        "#;
+
+        assert!(toml::from_str::<Config>(invalid_symbol).is_err());
+        assert!(toml::from_str::<Config>(too_many_decimals).is_err());
+        let duplicate_symbol = r#"
+            [[ethereum.tokens]]
+            name = "USD Coin"
+            symbol = "USDC"
+
+            [[ethereum.tokens]]
+            name = "Another USD Coin"
+            symbol = "usdc"
+        "#;
+        let decimals_overflow = r#"
+            [[ethereum.tokens]]
+            name = "USD Coin"
+            symbol = "USDC"
+            decimals = 78
+        "#;
+        let default_supply_overflows = r#"
+            [[ethereum.tokens]]
+            name = "USD Coin"
+            symbol = "USDC"
+            decimals = 77
+        "#;
+
+        assert!(toml::from_str::<Config>(allocation_exceeds_supply).is_err());
+        assert!(toml::from_str::<Config>(decimals_overflow).is_err());
+        assert!(toml::from_str::<Config>(duplicate_symbol).is_err());
+        assert!(toml::from_str::<Config>(default_supply_overflows).is_err());
+    }
+
+    #[test]
+    fn contracts_do_deserialize() {
+        let toml = r#"
+            [[ethereum.contracts]]
+            env = "REGISTRY_ADDRESS"
+            artifact = "build/contracts/Registry.json"
+
+            [[ethereum.contracts]]
+            env = "PRICE_ORACLE_ADDRESS"
+            bytecode = "build/PriceOracle.bin"
+            abi = "build/PriceOracle.abi"
+            constructor_args = "0x000000000000000000000000000000000000000000000000000000000000002a"
+            gas_limit = 3000000
+        "#;
+
+        let config = toml::from_str::<Config>(toml).unwrap();
+        let contracts = config.ethereum.unwrap().contracts;
+
+        assert_eq!(
+            contracts[0].source,
+            ContractSource::Artifact(PathBuf::from("build/contracts/Registry.json"))
+        );
+        assert!(contracts[0].constructor_args.is_empty());
+        assert_eq!(
+            contracts[1].source,
+            ContractSource::Files {
+                bytecode: PathBuf::from("build/PriceOracle.bin"),
+                abi: PathBuf::from("build/PriceOracle.abi"),
+            }
+        );
+        assert_eq!(contracts[1].constructor_args.len(), 32);
+        assert_eq!(contracts[1].gas_limit, 3_000_000);
+    }
+
+    #[test]
+    fn invalid_contracts_are_rejected() {
+        let lowercase_env = r#"
+            [[ethereum.contracts]]
+            env = "registry"
+            artifact = "Registry.json"
+        "#;
+        let artifact_and_bytecode = r#"
+            [[ethereum.contracts]]
+            env = "REGISTRY_ADDRESS"
+            artifact = "Registry.json"
+            bytecode = "Registry.bin"
+        "#;
+
+        let reserved_env = r#"
+            [[ethereum.contracts]]
+            env = "BITCOIN_HTTP_URI"
+            artifact = "Registry.json"
+        "#;
+
+        assert!(toml::from_str::<Config>(lowercase_env).is_err());
+        assert!(toml::from_str::<Config>(artifact_and_bytecode).is_err());
+        let token_env = r#"
+            [[ethereum.tokens]]
+            name = "USD Coin"
+            symbol = "usdc"
+
+            [[ethereum.contracts]]
+            env = "ERC20_USDC_CONTRACT_ADDRESS"
+            artifact = "Registry.json"
+        "#;
+        let duplicate_env = r#"
+            [[ethereum.contracts]]
+            env = "REGISTRY_ADDRESS"
+            artifact = "Registry.json"
+
+            [[ethereum.contracts]]
+            env = "REGISTRY_ADDRESS"
+            artifact = "Registry.json"
+        "#;
+
+        assert!(toml::from_str::<Config>(reserved_env).is_err());
+        assert!(toml::from_str::<Config>(token_env).is_err());
+        assert!(toml::from_str::<Config>(duplicate_env).is_err());
+    }
+
+    #[test]
+    fn parse_decimal_amounts() {
+        assert_eq!(
+            parse_decimal("1500", 6).unwrap(),
+            U256::from(1_500_000_000u64)
+        );
+        assert_eq!(parse_decimal("0.25", 2).unwrap(), U256::from(25u64));
+        assert!(parse_decimal("0.001", 2).is_err());
+        assert!(parse_decimal("1e3", 2).is_err());
+    }
 }
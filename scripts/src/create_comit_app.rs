@@ -3,6 +3,66 @@ use structopt::StructOpt;
 #[derive(StructOpt, Debug)]
 #[structopt(name = "create-comit-app")]
 pub enum CreateComitApp {
-    StartEnv,
+    StartEnv {
+        /// Derive all keys from this seed so that the environment is the same on every start
+        #[structopt(long)]
+        seed: Option<String>,
+    },
     ForceCleanEnv,
+    /// Mine blocks in the running environment
+    Mine(Mine),
+    /// Control the miner of the running environment
+    Mining(Mining),
+    /// Partition the Bitcoin nodes of the running environment, requires `second_node`
+    Network(Network),
+    /// Force a reorganisation of the given depth on the first Bitcoin node, requires `second_node`
+    Reorg {
+        depth: u32,
+    },
+    /// Roll back the last Bitcoin blocks of the first node
+    InvalidateBlocks {
+        blocks: u32,
+        /// Mine the same number of alternative blocks afterwards
+        #[structopt(long)]
+        remine: bool,
+    },
+    /// Reconsider all blocks rolled back with `invalidate-blocks`
+    ReconsiderBlocks,
+    /// Set the number of full blocks worth of transactions kept in the mempool, requires the fee
+    /// market
+    Congestion {
+        level: u32,
+    },
+    /// Move the clocks of both ledgers forward by a duration like 90s, 30m, 2h or 1d, requires
+    /// `node = "ganache"` for Ethereum
+    AdvanceTime {
+        duration: String,
+    },
+}
+
+#[derive(StructOpt, Debug)]
+pub enum Mine {
+    /// Mine the given number of Bitcoin blocks
+    Btc {
+        blocks: u32,
+        /// The Bitcoin node to mine on, `1` is the second node
+        #[structopt(long, default_value = "0")]
+        node: u32,
+    },
+}
+
+#[derive(StructOpt, Debug)]
+pub enum Mining {
+    /// Stop mining Bitcoin blocks until `mining resume` is called
+    Pause,
+    /// Resume mining Bitcoin blocks
+    Resume,
+}
+
+#[derive(StructOpt, Debug)]
+pub enum Network {
+    /// Disconnect the second Bitcoin node from the first one
+    Disconnect,
+    /// Reconnect the Bitcoin nodes
+    Connect,
 }
@@ -7,13 +7,16 @@ use anyhow::Context;
 use shiplift::ContainerOptions;
 
 use crate::docker::{
-    self, docker_daemon_ip, free_local_port::free_local_port, DockerImage, File, LogMessage,
-    DOCKER_NETWORK,
+    self, docker_daemon_ip, free_local_port::free_local_port, lnd::LndInstance, DockerImage, File,
+    LogMessage, DOCKER_NETWORK,
 };
 use serde::Serializer;
 
 const IMAGE: &str = "comitnetwork/cnd:0.8.0";
 
+/// The credentials of the actor's lnd are copied into this directory.
+const LND_DIR: &str = "/lnd";
+
 #[derive(derive_more::Display, Copy, Clone)]
 #[display(fmt = "http://{}:{}", ip, port)]
 pub struct HttpEndpoint {
@@ -25,8 +28,22 @@ pub struct CndInstance {
     pub http_endpoint: HttpEndpoint,
 }
 
-pub async fn new_instance(index: u32) -> anyhow::Result<CndInstance> {
-    let settings = Settings::default();
+pub async fn new_instance(
+    index: u32,
+    bitcoind_node_url: &str,
+    geth_node_url: &str,
+    lnd: Option<&LndInstance>,
+) -> anyhow::Result<CndInstance> {
+    let mut settings = Settings::default();
+    settings.bitcoin.bitcoind.node_url = bitcoind_node_url.to_owned();
+    settings.ethereum.geth.node_url = geth_node_url.to_owned();
+    settings.lightning = lnd.map(|lnd| Lightning {
+        network: "regtest".to_string(),
+        lnd: Lnd {
+            rest_api_url: lnd.internal_rest_url(),
+            dir: LND_DIR.to_string(),
+        },
+    });
 
     let settings = toml::to_string(&settings).context("failed to serialize settings")?;
 
@@ -34,20 +51,37 @@ pub async fn new_instance(index: u32) -> anyhow::Result<CndInstance> {
     options_builder.network_mode(DOCKER_NETWORK);
     options_builder.name(&format!("cnd_{}", index));
     options_builder.cmd(vec!["--", "cnd", "--config=/cnd.toml"]);
+    // external nodes on the host are reached through the host gateway
+    docker::add_host_gateway(&mut options_builder, &[bitcoind_node_url, geth_node_url]);
 
     let http_port = free_local_port().await?;
     options_builder.expose(8080, "tcp", http_port as u32);
 
     let options = options_builder.build();
 
+    let tls_cert_path = Path::new(LND_DIR).join("tls.cert");
+    let macaroon_path = Path::new(LND_DIR).join("data/chain/bitcoin/regtest/admin.macaroon");
+
+    let mut files = vec![File {
+        location: Path::new("/cnd.toml"),
+        content: settings.as_bytes(),
+    }];
+    if let Some(lnd) = lnd {
+        files.push(File {
+            location: &tls_cert_path,
+            content: &lnd.tls_cert,
+        });
+        files.push(File {
+            location: &macaroon_path,
+            content: &lnd.admin_macaroon,
+        });
+    }
+
     docker::start(
         DockerImage(IMAGE),
         options,
         LogMessage("Starting HTTP server on"),
-        vec![File {
-            location: Path::new("/cnd.toml"),
-            content: settings.as_bytes(),
-        }],
+        files,
     )
     .await?;
 
@@ -67,6 +101,8 @@ struct Settings {
     logging: Logging,
     bitcoin: Bitcoin,
     ethereum: Ethereum,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lightning: Option<Lightning>,
 }
 
 #[derive(Clone, Debug, serde::Serialize)]
@@ -120,6 +156,19 @@ struct Bitcoind {
     node_url: String,
 }
 
+#[derive(Clone, Debug, serde::Serialize)]
+struct Lightning {
+    network: String,
+    lnd: Lnd,
+}
+
+/// cnd reads `tls.cert` and the admin macaroon from `dir` which mirrors the lnd directory.
+#[derive(Clone, Debug, serde::Serialize)]
+struct Lnd {
+    rest_api_url: String,
+    dir: String,
+}
+
 #[derive(Clone, Debug, serde::Serialize)]
 struct Ethereum {
     chain_id: i16,
@@ -0,0 +1,138 @@
+use std::{net::Ipv4Addr, time::Duration};
+
+use anyhow::Context;
+use shiplift::ContainerOptions;
+use tokio::time::delay_for;
+
+use crate::{
+    bitcoind_rpc,
+    docker::{
+        self,
+        bitcoin::{self, BitcoindHttpEndpoint},
+        docker_daemon_ip,
+        free_local_port::preferred_or_free_local_port,
+        DockerImage, LogMessage, DOCKER_NETWORK,
+    },
+};
+
+/// Blockstream's fork of electrs which serves the Esplora REST API next to the Electrum protocol.
+const IMAGE: &str = "vulpemventures/electrs:v3.1.0";
+pub const CONTAINER_NAME: &str = "electrs";
+
+const ELECTRUM_PORT: u16 = 60401;
+const ESPLORA_HTTP_PORT: u16 = 3002;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const SYNC_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(derive_more::Display, Copy, Clone)]
+#[display(fmt = "{}:{}", ip, port)]
+pub struct ElectrumEndpoint {
+    port: u16,
+    ip: Ipv4Addr,
+}
+
+#[derive(derive_more::Display, Copy, Clone)]
+#[display(fmt = "http://{}:{}", ip, port)]
+pub struct EsploraHttpEndpoint {
+    port: u16,
+    ip: Ipv4Addr,
+}
+
+pub struct ElectrsInstance {
+    pub electrum_endpoint: ElectrumEndpoint,
+    pub esplora_endpoint: EsploraHttpEndpoint,
+}
+
+/// Starts electrs against the first bitcoind and waits until it indexed the chain tip.
+pub async fn new_instance(bitcoind: &BitcoindHttpEndpoint) -> anyhow::Result<ElectrsInstance> {
+    let credentials = bitcoind.credentials();
+
+    let mut options_builder = ContainerOptions::builder(IMAGE);
+    options_builder.name(CONTAINER_NAME);
+    options_builder.network_mode(DOCKER_NETWORK);
+    options_builder.entrypoint("/build/electrs");
+    let args = vec![
+        "-vvv".to_owned(),
+        "--network=regtest".to_owned(),
+        "--daemon-dir=/tmp".to_owned(),
+        format!(
+            "--daemon-rpc-addr={}:{}",
+            bitcoin::CONTAINER_NAME,
+            bitcoin::HTTP_PORT
+        ),
+        format!("--cookie={}:{}", credentials.username, credentials.password),
+        // the block files of bitcoind are not accessible from this container
+        "--jsonrpc-import".to_owned(),
+        format!("--electrum-rpc-addr=0.0.0.0:{}", ELECTRUM_PORT),
+        format!("--http-addr=0.0.0.0:{}", ESPLORA_HTTP_PORT),
+        "--cors=*".to_owned(),
+    ];
+    options_builder.cmd(args.iter().map(String::as_str).collect());
+
+    let electrum_port = preferred_or_free_local_port(ELECTRUM_PORT).await?;
+    options_builder.expose(u32::from(ELECTRUM_PORT), "tcp", u32::from(electrum_port));
+    let esplora_port = preferred_or_free_local_port(ESPLORA_HTTP_PORT).await?;
+    options_builder.expose(u32::from(ESPLORA_HTTP_PORT), "tcp", u32::from(esplora_port));
+
+    let options = options_builder.build();
+
+    docker::start(
+        DockerImage(IMAGE),
+        options,
+        LogMessage("Electrum RPC server running on"),
+        vec![],
+    )
+    .await?;
+
+    let esplora_endpoint = EsploraHttpEndpoint {
+        port: esplora_port,
+        ip: docker_daemon_ip()?,
+    };
+
+    tokio::time::timeout(
+        SYNC_TIMEOUT,
+        wait_until_synced(bitcoind.client(), esplora_endpoint),
+    )
+    .await
+    .context("electrs did not catch up with the chain tip in time")??;
+
+    Ok(ElectrsInstance {
+        electrum_endpoint: ElectrumEndpoint {
+            port: electrum_port,
+            ip: docker_daemon_ip()?,
+        },
+        esplora_endpoint,
+    })
+}
+
+async fn wait_until_synced(
+    node: bitcoind_rpc::Client,
+    esplora: EsploraHttpEndpoint,
+) -> anyhow::Result<()> {
+    loop {
+        let chain_tip = node.get_blockchain_info().await?.blocks;
+
+        // electrs answers with errors until it finished the initial sync
+        if let Ok(indexed_tip) = tip_height(esplora).await {
+            if indexed_tip >= chain_tip {
+                return Ok(());
+            }
+        }
+
+        delay_for(POLL_INTERVAL).await;
+    }
+}
+
+async fn tip_height(esplora: EsploraHttpEndpoint) -> anyhow::Result<u64> {
+    let height = reqwest::get(&format!("{}/blocks/tip/height", esplora))
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+
+    height
+        .trim()
+        .parse()
+        .with_context(|| format!("invalid tip height {}", height))
+}
@@ -1,20 +1,24 @@
-use std::net::Ipv4Addr;
-
 use crate::{
-    config,
+    config::{
+        self, EthereumNode, DEFAULT_ERC20_FUNDING, DEFAULT_ETHER_FUNDING_WEI, DEPLOY_GAS_LIMIT,
+    },
     docker::{self, docker_daemon_ip, DockerImage, LogMessage, DOCKER_NETWORK},
+    seed::Seed,
 };
 use anyhow::Context;
+use bip39::{Mnemonic, Seed as Bip39Seed};
 use lazy_static::lazy_static;
 use num256::Uint256;
-use secp256k1::{rand::thread_rng, SecretKey};
+use rust_bitcoin::util::bip32::{ChildNumber, ExtendedPrivKey};
+use secp256k1::{Secp256k1, SecretKey};
 use shiplift::ContainerOptions;
 use std::time::Duration;
+use tokio::time::delay_for;
 use web3::{
     api::Web3,
     confirm::send_transaction_with_confirmation,
     transports::Http,
-    types::{Address, Bytes, TransactionReceipt, TransactionRequest, H160, U256},
+    types::{Address, Bytes, TransactionReceipt, TransactionRequest, H160, H256, U256},
 };
 
 lazy_static! {
@@ -30,119 +34,304 @@ pub const TOKEN_CONTRACT: &str = include_str!("../../erc20_token/build/contract.
 pub const CONTRACT_ABI: &str = include_str!("../../erc20_token/build/abi.json");
 
 const IMAGE: &str = "ethereum/client-go:v1.9.18";
+pub const CONTAINER_NAME: &str = "ethereum";
+const GANACHE_IMAGE: &str = "trufflesuite/ganache-cli:v6.12.1";
+/// ganache only knows its own accounts, they need enough ether to fund everything else.
+const GANACHE_ACCOUNT_BALANCE_ETHER: &str = "1000000000";
+/// Prefix of the `web3_clientVersion` reported by ganache.
+const GANACHE_CLIENT_VERSION: &str = "EthereumJS TestRPC";
 
 const CHAIN_ID: &str = "1337";
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_millis(500);
 const HTTP_PORT: u16 = 8545;
 
-#[derive(derive_more::Display, Copy, Clone)]
-#[display(fmt = "http://{}:{}", ip, port)]
+#[derive(derive_more::Display, Clone)]
+#[display(fmt = "{}", url)]
 pub struct GethHttpEndpoint {
-    ip: Ipv4Addr,
-    port: u16,
+    /// Without trailing slash, e.g. `http://127.0.0.1:8545`
+    url: String,
 }
 
 pub struct GethInstance {
     pub http_endpoint: GethHttpEndpoint,
+    /// The URL of the node as seen from the cnd containers.
+    pub cnd_node_url: String,
     pub account_0: Account,
     pub account_1: Account,
     pub erc20_contract_address: Address,
+    /// The tokens of the `[[ethereum.tokens]]` section.
+    pub tokens: Vec<TokenContract>,
+    /// The contracts of the `[[ethereum.contracts]]` section.
+    pub contracts: Vec<DeployedContract>,
+    /// All ether and token transfers which fund the accounts and configured addresses.
+    pub funding_transactions: Vec<H256>,
 }
 
-pub async fn new_geth_instance(config: Option<config::Ethereum>) -> anyhow::Result<GethInstance> {
-    let mut options_builder = ContainerOptions::builder(IMAGE);
-    options_builder.name("ethereum");
-    options_builder.network_mode(DOCKER_NETWORK);
-    options_builder.cmd(vec![
-        "--dev",
-        "--dev.period=1", // generates a block every X seconds
-        format!("--networkid={}", CHAIN_ID).as_str(),
-        "--http",
-        "--http.addr=0.0.0.0",
-        format!("--http.port={}", HTTP_PORT).as_str(),
-        "--http.api=eth,net,web3,personal",
-        "--verbosity=4",
-        "--allow-insecure-unlock",
-    ]);
+pub struct TokenContract {
+    pub symbol: String,
+    pub address: Address,
+}
 
-    options_builder.expose(HTTP_PORT as u32, "tcp", HTTP_PORT as u32);
+/// A contract of the `[[ethereum.contracts]]` section, its address is written to `env`.
+pub struct DeployedContract {
+    pub env: String,
+    pub address: Address,
+}
 
-    let http_endpoint = GethHttpEndpoint {
-        port: HTTP_PORT,
-        ip: docker_daemon_ip()?,
-    };
+pub async fn new_geth_instance(
+    config: Option<config::Ethereum>,
+    seed: &Seed,
+) -> anyhow::Result<GethInstance> {
+    let config = config.unwrap_or_default();
 
-    let options = options_builder.build();
+    let (http_endpoint, cnd_node_url) = match &config.external {
+        Some(external) => {
+            anyhow::ensure!(
+                config.extra_args.is_empty() && config.override_args.is_empty(),
+                "extra_args and override_args are not supported by an external Ethereum node"
+            );
 
-    docker::start(
-        DockerImage(IMAGE),
-        options,
-        LogMessage("mined potential block"),
-        vec![],
-    )
-    .await
-    .context("failed to start container")?;
+            let url = external.http_url.trim_end_matches('/').to_owned();
+            let cnd_node_url = docker::container_url(&url)?;
+
+            (GethHttpEndpoint { url }, cnd_node_url)
+        }
+        None => (
+            new_container(&config).await?,
+            format!("http://{}:{}", CONTAINER_NAME, HTTP_PORT),
+        ),
+    };
 
-    let account_0 = fund_new_account(http_endpoint)
+    let (account_0, funding_0) = fund_new_account(&http_endpoint, &seed.mnemonic(0)?)
         .await
         .context("failed to fund first account")?;
-    let account_1 = fund_new_account(http_endpoint)
+    let (account_1, funding_1) = fund_new_account(&http_endpoint, &seed.mnemonic(1)?)
         .await
         .context("failed to fund second account")?;
+    let mut funding_transactions = vec![funding_0, funding_1];
 
-    fund_address(http_endpoint, *DEPLOY_ACCOUNT)
-        .await
-        .context("Failed to fund contract deployment account.")?;
+    fund_address(
+        &http_endpoint,
+        *DEPLOY_ACCOUNT,
+        U256::from(DEFAULT_ETHER_FUNDING_WEI),
+    )
+    .await
+    .context("Failed to fund contract deployment account.")?;
+
+    let addresses_to_fund = config.addresses_to_fund;
 
-    if let Some(config) = config.clone() {
-        for address in config.addresses_to_fund {
-            fund_address(http_endpoint, address)
+    for funding in &addresses_to_fund {
+        if let Some(eth) = funding.eth {
+            let transaction = fund_address(&http_endpoint, funding.address, eth)
                 .await
                 .context("failed to fund config account")?;
+            funding_transactions.push(transaction);
         }
     }
 
-    let mut addresses = config
-        .map(|config| config.addresses_to_fund)
-        .unwrap_or_default();
+    let mut erc20_funding = addresses_to_fund
+        .iter()
+        .filter_map(|funding| funding.erc20.map(|amount| (funding.address, amount)))
+        .collect::<Vec<_>>();
+
+    erc20_funding.push((
+        derive_address(account_0)?,
+        U256::from(DEFAULT_ERC20_FUNDING),
+    ));
+    erc20_funding.push((
+        derive_address(account_1)?,
+        U256::from(DEFAULT_ERC20_FUNDING),
+    ));
+    let (contract_address, erc20_transactions) =
+        new_erc20_contract(&http_endpoint, &config::Token::erc20(), erc20_funding).await?;
+    funding_transactions.extend(erc20_transactions);
+
+    let mut tokens: Vec<TokenContract> = Vec::new();
+    for token in &config.tokens {
+        let funding = vec![
+            (derive_address(account_0)?, token.allocation(0)),
+            (derive_address(account_1)?, token.allocation(1)),
+        ];
+        let (address, transactions) = new_erc20_contract(&http_endpoint, token, funding)
+            .await
+            .with_context(|| format!("failed to deploy token {}", token.symbol))?;
+        funding_transactions.extend(transactions);
+
+        tokens.push(TokenContract {
+            symbol: token.symbol.clone(),
+            address,
+        });
+    }
+
+    let mut contracts: Vec<DeployedContract> = Vec::new();
+    for contract in &config.contracts {
+        let address = deploy_contract(&http_endpoint, contract)
+            .await
+            .with_context(|| format!("failed to deploy contract {}", contract.env))?;
 
-    addresses.push(derive_address(account_0)?);
-    addresses.push(derive_address(account_1)?);
-    let contract_address = new_erc20_contract(http_endpoint, addresses).await?;
+        contracts.push(DeployedContract {
+            env: contract.env.clone(),
+            address,
+        });
+    }
 
     Ok(GethInstance {
         http_endpoint,
         account_0,
         account_1,
+        cnd_node_url,
         erc20_contract_address: contract_address,
+        tokens,
+        contracts,
+        funding_transactions,
     })
 }
 
+/// Merges the configured arguments into the defaults of the node. Funding and deployment need
+/// the HTTP API with the `eth`, `personal` and `web3` namespaces.
+fn node_args(config: &config::Ethereum, defaults: Vec<String>) -> anyhow::Result<Vec<String>> {
+    let protected: &[&str] = match config.node {
+        EthereumNode::Geth => &[
+            "dev",
+            "dev.period",
+            "networkid",
+            "http",
+            "http.addr",
+            "http.port",
+            "allow-insecure-unlock",
+        ],
+        EthereumNode::Ganache => &["host", "port", "networkId", "chainId"],
+    };
+    let args = docker::merge_args(
+        defaults,
+        protected,
+        &config.extra_args,
+        &config.override_args,
+    )?;
+
+    if config.node == EthereumNode::Geth {
+        let apis = args
+            .iter()
+            .find_map(|arg| arg.strip_prefix("--http.api="))
+            .unwrap_or_default()
+            .split(',')
+            .collect::<Vec<_>>();
+        for api in &["eth", "personal", "web3"] {
+            anyhow::ensure!(
+                apis.contains(api),
+                "--http.api has to include {}, comit-scripts depends on it",
+                api
+            );
+        }
+    }
+
+    Ok(args)
+}
+
+/// Starts the configured Ethereum client in the `ethereum` container.
+async fn new_container(config: &config::Ethereum) -> anyhow::Result<GethHttpEndpoint> {
+    let (image, args, ready_message) = match config.node {
+        EthereumNode::Geth => (
+            IMAGE,
+            vec![
+                "--dev".to_owned(),
+                "--dev.period=1".to_owned(), // generates a block every X seconds
+                format!("--networkid={}", CHAIN_ID),
+                "--http".to_owned(),
+                "--http.addr=0.0.0.0".to_owned(),
+                format!("--http.port={}", HTTP_PORT),
+                "--http.api=eth,net,web3,personal".to_owned(),
+                "--verbosity=4".to_owned(),
+                "--allow-insecure-unlock".to_owned(),
+            ],
+            "mined potential block",
+        ),
+        EthereumNode::Ganache => (
+            GANACHE_IMAGE,
+            vec![
+                "--host=0.0.0.0".to_owned(),
+                format!("--port={}", HTTP_PORT),
+                format!("--networkId={}", CHAIN_ID),
+                format!("--chainId={}", CHAIN_ID),
+                "--blockTime=1".to_owned(),
+                "--gasPrice=0".to_owned(),
+                format!("--defaultBalanceEther={}", GANACHE_ACCOUNT_BALANCE_ETHER),
+            ],
+            "Listening on",
+        ),
+    };
+    let args = node_args(config, args).context("invalid Ethereum node arguments")?;
+
+    let mut options_builder = ContainerOptions::builder(image);
+    options_builder.name(CONTAINER_NAME);
+    options_builder.network_mode(DOCKER_NETWORK);
+    options_builder.cmd(args.iter().map(String::as_str).collect());
+
+    options_builder.expose(HTTP_PORT as u32, "tcp", HTTP_PORT as u32);
+
+    let http_endpoint = GethHttpEndpoint {
+        url: format!("http://{}:{}", docker_daemon_ip()?, HTTP_PORT),
+    };
+
+    let options = options_builder.build();
+
+    docker::start(
+        DockerImage(image),
+        options,
+        LogMessage(ready_message),
+        vec![],
+    )
+    .await
+    .context("failed to start container")?;
+
+    Ok(http_endpoint)
+}
+
 #[derive(Clone, Copy)]
 pub struct Account {
     pub private_key: SecretKey,
 }
 
 impl Account {
-    fn new_random() -> Self {
-        Self {
-            private_key: SecretKey::new(&mut thread_rng()),
-        }
+    /// Derives the key at m/44'/60'/0'/0/0 like Ethereum wallets do.
+    fn from_mnemonic(mnemonic: &Mnemonic) -> anyhow::Result<Self> {
+        let seed = Bip39Seed::new(mnemonic, "");
+        // the network is irrelevant, only the derived private key is used
+        let master = ExtendedPrivKey::new_master(rust_bitcoin::Network::Regtest, seed.as_bytes())
+            .context("failed to generate new extended private key from seed")?;
+
+        let derivation_path = [
+            ChildNumber::from_hardened_idx(44)?,
+            ChildNumber::from_hardened_idx(60)?,
+            ChildNumber::from_hardened_idx(0)?,
+            ChildNumber::from_normal_idx(0)?,
+            ChildNumber::from_normal_idx(0)?,
+        ];
+        let private_key = master
+            .derive_priv(&Secp256k1::new(), &derivation_path)?
+            .private_key
+            .key;
+
+        Ok(Self { private_key })
     }
 }
 
-async fn fund_new_account(endpoint: GethHttpEndpoint) -> anyhow::Result<Account> {
+async fn fund_new_account(
+    endpoint: &GethHttpEndpoint,
+    mnemonic: &Mnemonic,
+) -> anyhow::Result<(Account, H256)> {
     let transport = Http::new(&endpoint.to_string())
         .context("unable to initialize http transport to ethereum node")?;
     let client = Web3::new(transport);
 
-    let account = Account::new_random();
+    let account = Account::from_mnemonic(mnemonic)?;
     let address = derive_address(account)?;
 
-    send_transaction(
+    let receipt = send_transaction(
         client,
         Some(address),
         30_000,
-        U256::from(1000u128 * 10u128.pow(18)),
+        U256::from(DEFAULT_ETHER_FUNDING_WEI),
         Vec::new(),
     )
     .await
@@ -154,79 +343,241 @@ async fn fund_new_account(endpoint: GethHttpEndpoint) -> anyhow::Result<Account>
         )
     })?;
 
-    Ok(account)
+    Ok((account, receipt.transaction_hash))
 }
 
-async fn fund_address(endpoint: GethHttpEndpoint, address: Address) -> anyhow::Result<()> {
+pub async fn fund_address(
+    endpoint: &GethHttpEndpoint,
+    address: Address,
+    amount: U256,
+) -> anyhow::Result<H256> {
     let transport = Http::new(&endpoint.to_string())
         .context("unable to initialize http transport to ethereum node")?;
     let client = Web3::new(transport);
 
-    send_transaction(
-        client.clone(),
-        Some(address),
-        30_000,
-        U256::from(1000u128 * 10u128.pow(18)),
-        Vec::new(),
+    let receipt = send_transaction(client.clone(), Some(address), 30_000, amount, Vec::new())
+        .await
+        .with_context(|| {
+            format!(
+                "failed to send transaction for funding account {:x} with ether to {}",
+                address,
+                endpoint.to_string()
+            )
+        })?;
+
+    Ok(receipt.transaction_hash)
+}
+
+/// Deploys an ERC20 contract and distributes the tokens, returns the contract address and the
+/// transfer transactions.
+async fn new_erc20_contract(
+    endpoint: &GethHttpEndpoint,
+    token: &config::Token,
+    funding: Vec<(Address, U256)>,
+) -> anyhow::Result<(Address, Vec<H256>)> {
+    let transport = Http::new(&endpoint.to_string())?;
+    let client = Web3::new(transport);
+
+    let contract_address = deploy_erc20_contract(client, token).await?;
+
+    let mut transactions = Vec::new();
+    for (address, amount) in funding {
+        transactions.push(transfer_erc20(endpoint, contract_address, address, amount).await?);
+    }
+
+    Ok((contract_address, transactions))
+}
+
+/// Waits until every transaction has at least `confirmations` confirmations, the node mines a
+/// block every second.
+pub async fn wait_for_confirmations(
+    endpoint: &GethHttpEndpoint,
+    transactions: &[H256],
+    confirmations: u32,
+) -> anyhow::Result<()> {
+    let transport = Http::new(&endpoint.to_string())?;
+    let client = Web3::new(transport);
+
+    let wait = async {
+        for transaction in transactions {
+            loop {
+                let receipt = client.eth().transaction_receipt(*transaction).await?;
+                let latest_block = client.eth().block_number().await?.as_u64();
+
+                let mined_in = receipt.and_then(|receipt| receipt.block_number);
+                if let Some(mined_in) = mined_in {
+                    if latest_block + 1 >= mined_in.as_u64() + u64::from(confirmations) {
+                        break;
+                    }
+                }
+
+                delay_for(CONFIRMATION_POLL_INTERVAL).await;
+            }
+        }
+
+        Ok::<_, anyhow::Error>(())
+    };
+
+    // one block per second plus some slack for slow machines
+    let timeout = Duration::from_secs(30 + 2 * u64::from(confirmations));
+    tokio::time::timeout(timeout, wait).await.with_context(|| {
+        format!(
+            "funding transactions did not reach {} confirmations in time",
+            confirmations
+        )
+    })?
+}
+
+/// Transfers `amount` tokens of the ERC20 contract from the deployment account to `address`.
+pub async fn transfer_erc20(
+    endpoint: &GethHttpEndpoint,
+    contract_address: Address,
+    address: Address,
+    amount: U256,
+) -> anyhow::Result<H256> {
+    let transport = Http::new(&endpoint.to_string())?;
+    let client = Web3::new(transport);
+
+    let transfer = transfer_fn(
+        clarity::Address::from(address.0),
+        Uint256::from(<[u8; 32]>::from(amount)),
+    );
+    let receipt = send_raw_transaction(
+        client,
+        Some(clarity::Address::from(contract_address.0)),
+        100_000,
+        U256::from(0u64),
+        transfer,
     )
     .await
     .with_context(|| {
         format!(
-            "failed to send transaction for funding account {:x} with ether to {}",
+            "failed to send transaction for funding account {:x} with erc20 to {}",
             address,
             endpoint.to_string()
         )
     })?;
 
-    Ok(())
+    Ok(receipt.transaction_hash)
 }
 
-async fn new_erc20_contract(
-    endpoint: GethHttpEndpoint,
-    addresses: Vec<Address>,
+async fn deploy_erc20_contract(
+    client: Web3<Http>,
+    token: &config::Token,
 ) -> anyhow::Result<Address> {
+    let data = TOKEN_CONTRACT[2..].trim(); // remove the 0x in the front and any whitespace
+    let mut erc20_contract = hex::decode(data).context("token contract should be valid hex")?;
+    erc20_contract.extend(token_constructor_args(token));
+
+    let receipt = send_raw_transaction(
+        client,
+        None,
+        DEPLOY_GAS_LIMIT,
+        U256::from(0),
+        erc20_contract,
+    )
+    .await?;
+
+    let contract_address = receipt
+        .contract_address
+        .context("contract_address not present, invalid deployment transaction?")?;
+
+    Ok(contract_address)
+}
+
+/// Deploys a compiled contract with its constructor arguments from the deployment account.
+async fn deploy_contract(
+    endpoint: &GethHttpEndpoint,
+    contract: &config::Contract,
+) -> anyhow::Result<Address> {
+    let (bytecode, abi) = match &contract.source {
+        config::ContractSource::Artifact(path) => {
+            let artifact = read_json(path).await?;
+            let bytecode = match &artifact["bytecode"] {
+                serde_json::Value::String(bytecode) => bytecode.clone(),
+                // Foundry wraps the bytecode in an object
+                bytecode => bytecode["object"]
+                    .as_str()
+                    .with_context(|| format!("{} has no bytecode", path.display()))?
+                    .to_owned(),
+            };
+
+            (bytecode, artifact["abi"].clone())
+        }
+        config::ContractSource::Files { bytecode, abi } => {
+            let bytecode = tokio::fs::read_to_string(bytecode)
+                .await
+                .with_context(|| format!("failed to read {}", bytecode.display()))?;
+
+            (bytecode, read_json(abi).await?)
+        }
+    };
+
+    let mut data = parse_bytecode(&bytecode)?;
+
+    // arguments which do not match the constructor would only surface as a failed deployment
+    let inputs = constructor_inputs(&abi)?;
+    if inputs == 0 && !contract.constructor_args.is_empty() {
+        anyhow::bail!("the constructor takes no arguments but constructor_args are given")
+    }
+    if inputs > 0 && contract.constructor_args.is_empty() {
+        anyhow::bail!(
+            "the constructor takes {} arguments, set constructor_args",
+            inputs
+        )
+    }
+    data.extend(&contract.constructor_args);
+
     let transport = Http::new(&endpoint.to_string())?;
     let client = Web3::new(transport);
+    let receipt =
+        send_raw_transaction(client, None, contract.gas_limit, U256::from(0), data).await?;
 
-    let contract_address = deploy_erc20_contract(client.clone()).await?;
-
-    for address in addresses {
-        let transfer = transfer_fn(
-            clarity::Address::from(address.0),
-            Uint256::from(100000u128) * Uint256::from(10u128.pow(18)),
-        );
-        send_raw_transaction(
-            client.clone(),
-            Some(clarity::Address::from(contract_address.0)),
-            100_000,
-            U256::from(0u64),
-            transfer,
+    if receipt.status.map_or(false, |status| status.is_zero()) {
+        anyhow::bail!(
+            "the deployment transaction {:#x} reverted",
+            receipt.transaction_hash
         )
-        .await
-        .with_context(|| {
-            format!(
-                "failed to send transaction for funding account {:x} with erc20 to {}",
-                address,
-                endpoint.to_string()
-            )
-        })?;
     }
 
-    Ok(contract_address)
+    receipt
+        .contract_address
+        .context("contract_address not present, invalid deployment transaction?")
 }
 
-async fn deploy_erc20_contract(client: Web3<Http>) -> anyhow::Result<Address> {
-    let data = TOKEN_CONTRACT[2..].trim(); // remove the 0x in the front and any whitespace
-    let erc20_contract = hex::decode(data).context("token contract should be valid hex")?;
+async fn read_json(path: &std::path::Path) -> anyhow::Result<serde_json::Value> {
+    let json = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("failed to read {}", path.display()))?;
 
-    let receipt =
-        send_raw_transaction(client, None, 10_000_000, U256::from(0), erc20_contract).await?;
+    serde_json::from_str(&json).with_context(|| format!("{} is not valid JSON", path.display()))
+}
 
-    let contract_address = receipt
-        .contract_address
-        .context("contract_address not present, invalid deployment transaction?")?;
+fn parse_bytecode(bytecode: &str) -> anyhow::Result<Vec<u8>> {
+    let bytecode = bytecode.trim().trim_start_matches("0x");
 
-    Ok(contract_address)
+    if bytecode.is_empty() {
+        anyhow::bail!("the bytecode is empty, is the contract abstract?")
+    }
+    // solc leaves placeholders like `__$...$__` for libraries which are not linked yet
+    if bytecode.contains("__") {
+        anyhow::bail!("the bytecode references libraries which are not linked")
+    }
+
+    hex::decode(bytecode).context("the bytecode is not valid hex")
+}
+
+/// The number of constructor arguments, a contract without constructor takes none.
+fn constructor_inputs(abi: &serde_json::Value) -> anyhow::Result<usize> {
+    let entries = abi.as_array().context("the ABI is not a JSON array")?;
+
+    let inputs = entries
+        .iter()
+        .find(|entry| entry["type"] == "constructor")
+        .and_then(|constructor| constructor["inputs"].as_array())
+        .map_or(0, Vec::len);
+
+    Ok(inputs)
 }
 
 async fn send_raw_transaction(
@@ -271,6 +622,35 @@ async fn get_chain_id(client: Web3<Http>) -> anyhow::Result<u16> {
         .with_context(|| format!("{} is not a valid chain-id", network))
 }
 
+/// ABI encodes the arguments of `constructor(string name, string symbol, uint8 decimals, uint256
+/// supply)` which are appended to the bytecode of the token.
+fn token_constructor_args(token: &config::Token) -> Vec<u8> {
+    fn word(value: U256) -> [u8; 32] {
+        <[u8; 32]>::from(value)
+    }
+    fn string(value: &str) -> Vec<u8> {
+        let mut encoded = word(U256::from(value.len())).to_vec();
+        encoded.extend(value.as_bytes());
+        // strings are right-padded to a multiple of 32 bytes
+        encoded.resize(32 + (value.len() + 31) / 32 * 32, 0);
+        encoded
+    }
+
+    let name = string(&token.name);
+    let symbol = string(&token.symbol);
+
+    // the head holds the offsets of the strings which follow the four head words
+    let mut args = Vec::new();
+    args.extend(&word(U256::from(4 * 32)));
+    args.extend(&word(U256::from(4 * 32 + name.len())));
+    args.extend(&word(U256::from(token.decimals)));
+    args.extend(&word(token.supply));
+    args.extend(name);
+    args.extend(symbol);
+
+    args
+}
+
 fn transfer_fn(address: clarity::Address, amount: Uint256) -> Vec<u8> {
     clarity::abi::encode_call(
         "transfer(address,uint256)",
@@ -289,11 +669,18 @@ async fn send_transaction(
     data: Vec<u8>,
 ) -> anyhow::Result<TransactionReceipt> {
     let dev_account = client.eth().coinbase().await?;
-    let unlock = client
-        .personal()
-        .unlock_account(dev_account, "", None)
-        .await?;
-    if !unlock {
+    // ganache keeps its accounts unlocked and refuses to unlock them again
+    let is_ganache = client
+        .web3()
+        .client_version()
+        .await?
+        .starts_with(GANACHE_CLIENT_VERSION);
+    if !is_ganache
+        && !client
+            .personal()
+            .unlock_account(dev_account, "", None)
+            .await?
+    {
         anyhow::bail!("Failed to unlock dev-account")
     }
 
@@ -337,3 +724,80 @@ fn derive_address(account: Account) -> anyhow::Result<Address> {
     data.copy_from_slice(&address.as_bytes());
     Ok(H160(data))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_constructor_args_are_abi_encoded() {
+        let token = config::Token {
+            name: "USD Coin".to_owned(),
+            symbol: "USDC".to_owned(),
+            decimals: 6,
+            supply: U256::from(1000),
+            allocation: U256::from(0),
+            actor_allocations: Vec::new(),
+        };
+
+        let expected = [
+            "0000000000000000000000000000000000000000000000000000000000000080",
+            "00000000000000000000000000000000000000000000000000000000000000c0",
+            "0000000000000000000000000000000000000000000000000000000000000006",
+            "00000000000000000000000000000000000000000000000000000000000003e8",
+            "0000000000000000000000000000000000000000000000000000000000000008",
+            "55534420436f696e000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000004",
+            "5553444300000000000000000000000000000000000000000000000000000000",
+        ]
+        .concat();
+
+        assert_eq!(hex::encode(token_constructor_args(&token)), expected);
+    }
+
+    #[test]
+    fn embedded_abi_matches_token_constructor() {
+        let abi: serde_json::Value = serde_json::from_str(CONTRACT_ABI).unwrap();
+        let constructor = abi
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|entry| entry["type"] == "constructor")
+            .unwrap();
+
+        let inputs = constructor["inputs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|input| input["type"].as_str().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(inputs, vec!["string", "string", "uint8", "uint256"]);
+    }
+
+    #[test]
+    fn bytecode_is_parsed_with_or_without_prefix() {
+        assert_eq!(parse_bytecode("0x6080\n").unwrap(), vec![0x60, 0x80]);
+        assert_eq!(parse_bytecode("6080").unwrap(), vec![0x60, 0x80]);
+        assert!(parse_bytecode("0x").is_err());
+        assert!(parse_bytecode("0x6080__$53aea86b7d70b31448b230b20ae141a537$__").is_err());
+    }
+
+    #[test]
+    fn constructor_inputs_are_counted() {
+        let abi = serde_json::json!([
+            { "type": "function", "name": "owner", "inputs": [] },
+            {
+                "type": "constructor",
+                "inputs": [
+                    { "name": "owner", "type": "address" },
+                    { "name": "fee", "type": "uint256" }
+                ]
+            }
+        ]);
+
+        assert_eq!(constructor_inputs(&abi).unwrap(), 2);
+        assert_eq!(constructor_inputs(&serde_json::json!([])).unwrap(), 0);
+        assert!(constructor_inputs(&serde_json::json!({})).is_err());
+    }
+}
@@ -12,3 +12,29 @@ pub async fn free_local_port() -> anyhow::Result<u16> {
 
     Ok(socket_addr.port())
 }
+
+/// Returns `preferred` if it is free so that ports are stable across runs, any free port otherwise.
+pub async fn preferred_or_free_local_port(preferred: u16) -> anyhow::Result<u16> {
+    let socket = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, preferred));
+
+    match TcpListener::bind(&socket).await {
+        Ok(_) => Ok(preferred),
+        Err(_) => free_local_port().await,
+    }
+}
+
+/// Binds `preferred` if it is free, any free port otherwise, and keeps the listener so that the
+/// port cannot be taken before it is served.
+pub async fn bind_preferred_or_free_local_port(preferred: u16) -> anyhow::Result<TcpListener> {
+    let socket = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, preferred));
+
+    match TcpListener::bind(&socket).await {
+        Ok(listener) => Ok(listener),
+        Err(_) => {
+            let socket = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0));
+            TcpListener::bind(&socket)
+                .await
+                .with_context(|| format!("unable to bind to {}", socket))
+        }
+    }
+}
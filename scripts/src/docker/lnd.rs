@@ -0,0 +1,422 @@
+use std::{convert::TryFrom, net::Ipv4Addr, path::Path, time::Duration};
+
+use anyhow::Context;
+use rust_bitcoin::Address;
+use shiplift::ContainerOptions;
+use tokio::time::delay_for;
+
+use crate::{
+    bitcoind_rpc::{self, Credentials},
+    config,
+    docker::{
+        self,
+        bitcoin::{self, HTTP_PORT as BITCOIND_HTTP_PORT, ZMQ_RAW_BLOCK_PORT, ZMQ_RAW_TX_PORT},
+        docker_daemon_ip,
+        free_local_port::preferred_or_free_local_port,
+        DockerImage, LogMessage, DOCKER_NETWORK,
+    },
+};
+
+const IMAGE: &str = "lightninglabs/lnd:v0.11.1-beta";
+
+/// lnd `n` exposes its REST API on `REST_PORT + n` and its gRPC API on `GRPC_PORT + n` if
+/// those ports are free
+const REST_PORT: u16 = 8090;
+const GRPC_PORT: u16 = 10009;
+const P2P_PORT: u16 = 9735;
+
+const LND_DIR: &str = "/root/.lnd";
+/// Confirmations lnd waits for before a channel can be used on regtest.
+const CHANNEL_CONFIRMATIONS: u32 = 6;
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const POLL_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(derive_more::Display, Clone)]
+#[display(fmt = "https://{}:{}", ip, port)]
+pub struct LndRestEndpoint {
+    port: u16,
+    ip: Ipv4Addr,
+}
+
+#[derive(derive_more::Display, Clone)]
+#[display(fmt = "{}:{}", ip, port)]
+pub struct LndGrpcEndpoint {
+    port: u16,
+    ip: Ipv4Addr,
+}
+
+pub struct LndInstance {
+    pub name: String,
+    pub rest_endpoint: LndRestEndpoint,
+    pub grpc_endpoint: LndGrpcEndpoint,
+    pub identity_pubkey: String,
+    pub tls_cert: Vec<u8>,
+    pub admin_macaroon: Vec<u8>,
+}
+
+impl LndInstance {
+    /// The REST API inside the docker network, used by the cnds.
+    pub fn internal_rest_url(&self) -> String {
+        format!("https://{}:8080", self.name)
+    }
+
+    fn client(&self) -> anyhow::Result<LndClient> {
+        LndClient::new(self.rest_endpoint.to_string(), &self.admin_macaroon)
+    }
+}
+
+/// Starts an lnd per actor, funds both on-chain and opens a confirmed channel from the first to
+/// the second one.
+pub async fn new_instances(
+    config: config::Lightning,
+    credentials: &Credentials,
+    funding_wallet: &bitcoind_rpc::Client,
+) -> anyhow::Result<(LndInstance, LndInstance)> {
+    let lnd_0 = new_instance(0, credentials)
+        .await
+        .context("failed to start first lnd")?;
+    let lnd_1 = new_instance(1, credentials)
+        .await
+        .context("failed to start second lnd")?;
+
+    let client_0 = &lnd_0.client()?;
+    let client_1 = &lnd_1.client()?;
+
+    for client in &[client_0, client_1] {
+        let address = client.new_address().await?;
+        funding_wallet
+            .send_to_address(&address, config.funding)
+            .await
+            .context("failed to fund lnd")?;
+    }
+    mine(funding_wallet, 1).await?;
+
+    for client in &[client_0, client_1] {
+        poll_until(|| async move {
+            let balance = client.confirmed_balance().await?;
+            Ok(if balance >= config.funding.as_sat() {
+                Some(())
+            } else {
+                None
+            })
+        })
+        .await
+        .context("lnd did not see its funding")?;
+    }
+
+    client_0
+        .connect(
+            &lnd_1.identity_pubkey,
+            &format!("{}:{}", lnd_1.name, P2P_PORT),
+        )
+        .await
+        .context("failed to connect lnds")?;
+    client_0
+        .open_channel(
+            &lnd_1.identity_pubkey,
+            config.channel_capacity.as_sat(),
+            config.push_amount.as_sat(),
+        )
+        .await
+        .context("failed to open channel")?;
+    mine(funding_wallet, CHANNEL_CONFIRMATIONS).await?;
+
+    poll_until(|| async move {
+        let channels_0 = client_0.active_channels().await?;
+        let channels_1 = client_1.active_channels().await?;
+        Ok(if channels_0 > 0 && channels_1 > 0 {
+            Some(())
+        } else {
+            None
+        })
+    })
+    .await
+    .context("channel did not become active")?;
+
+    Ok((lnd_0, lnd_1))
+}
+
+async fn new_instance(index: u32, credentials: &Credentials) -> anyhow::Result<LndInstance> {
+    let name = format!("lnd_{}", index);
+
+    let mut options_builder = ContainerOptions::builder(IMAGE);
+    options_builder.name(&name);
+    options_builder.network_mode(DOCKER_NETWORK);
+    let args = vec![
+        "--bitcoin.active".to_owned(),
+        "--bitcoin.regtest".to_owned(),
+        "--bitcoin.node=bitcoind".to_owned(),
+        format!(
+            "--bitcoind.rpchost={}:{}",
+            bitcoin::CONTAINER_NAME,
+            BITCOIND_HTTP_PORT
+        ),
+        format!("--bitcoind.rpcuser={}", credentials.username),
+        format!("--bitcoind.rpcpass={}", credentials.password),
+        format!(
+            "--bitcoind.zmqpubrawblock=tcp://{}:{}",
+            bitcoin::CONTAINER_NAME,
+            ZMQ_RAW_BLOCK_PORT
+        ),
+        format!(
+            "--bitcoind.zmqpubrawtx=tcp://{}:{}",
+            bitcoin::CONTAINER_NAME,
+            ZMQ_RAW_TX_PORT
+        ),
+        "--noseedbackup".to_owned(),
+        "--restlisten=0.0.0.0:8080".to_owned(),
+        "--rpclisten=0.0.0.0:10009".to_owned(),
+        format!("--listen=0.0.0.0:{}", P2P_PORT),
+        format!("--tlsextradomain={}", name),
+        format!("--alias={}", name),
+    ];
+    options_builder.cmd(args.iter().map(String::as_str).collect());
+
+    let port_offset = u16::try_from(index)?;
+    let rest_port = preferred_or_free_local_port(REST_PORT + port_offset).await?;
+    options_builder.expose(8080, "tcp", u32::from(rest_port));
+    let grpc_port = preferred_or_free_local_port(GRPC_PORT + port_offset).await?;
+    options_builder.expose(10009, "tcp", u32::from(grpc_port));
+
+    let options = options_builder.build();
+
+    docker::start(
+        DockerImage(IMAGE),
+        options,
+        LogMessage("gRPC proxy started at"),
+        vec![],
+    )
+    .await?;
+
+    let tls_cert = docker::read_file(&name, &Path::new(LND_DIR).join("tls.cert")).await?;
+    let admin_macaroon = docker::read_file(
+        &name,
+        &Path::new(LND_DIR).join("data/chain/bitcoin/regtest/admin.macaroon"),
+    )
+    .await?;
+
+    let rest_endpoint = LndRestEndpoint {
+        port: rest_port,
+        ip: docker_daemon_ip()?,
+    };
+    let client = &LndClient::new(rest_endpoint.to_string(), &admin_macaroon)?;
+
+    let identity_pubkey = poll_until(|| async move {
+        let info = client.get_info().await?;
+        Ok(if info.synced_to_chain {
+            Some(info.identity_pubkey)
+        } else {
+            None
+        })
+    })
+    .await
+    .context("lnd did not sync to the chain")?;
+
+    Ok(LndInstance {
+        name,
+        rest_endpoint,
+        grpc_endpoint: LndGrpcEndpoint {
+            port: grpc_port,
+            ip: docker_daemon_ip()?,
+        },
+        identity_pubkey,
+        tls_cert,
+        admin_macaroon,
+    })
+}
+
+async fn mine(wallet: &bitcoind_rpc::Client, blocks: u32) -> anyhow::Result<()> {
+    let address = wallet.get_new_address("bech32").await?;
+    wallet
+        .generate_to_address(blocks, &address)
+        .await
+        .context("failed to generate blocks")?;
+
+    Ok(())
+}
+
+/// Polls `f` until it returns `Some`, fails after `POLL_TIMEOUT`.
+async fn poll_until<F, Fut, T>(f: F) -> anyhow::Result<T>
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = anyhow::Result<Option<T>>>,
+{
+    let poll = async {
+        loop {
+            // lnd answers with errors while it is still starting up
+            if let Ok(Some(value)) = f().await {
+                return value;
+            }
+
+            delay_for(POLL_INTERVAL).await;
+        }
+    };
+
+    tokio::time::timeout(POLL_TIMEOUT, poll)
+        .await
+        .context("timed out waiting for lnd")
+}
+
+/// A minimal client for the REST API of lnd.
+struct LndClient {
+    url: String,
+    macaroon: String,
+    http: reqwest::Client,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct GetInfoResponse {
+    identity_pubkey: String,
+    #[serde(default)]
+    synced_to_chain: bool,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct NewAddressResponse {
+    address: Address,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct WalletBalanceResponse {
+    /// int64 values are encoded as strings by the REST API
+    #[serde(default)]
+    confirmed_balance: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ListChannelsResponse {
+    #[serde(default)]
+    channels: Vec<Channel>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Channel {
+    #[serde(default)]
+    active: bool,
+}
+
+impl LndClient {
+    fn new(url: String, macaroon: &[u8]) -> anyhow::Result<Self> {
+        // lnd uses a self-signed certificate which is only valid for its own host names
+        let http = reqwest::Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .context("failed to build http client for lnd")?;
+
+        Ok(LndClient {
+            url,
+            macaroon: hex::encode(macaroon),
+            http,
+        })
+    }
+
+    async fn get<T>(&self, path: &str) -> anyhow::Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let response = self
+            .http
+            .get(&format!("{}{}", self.url, path))
+            .header("Grpc-Metadata-macaroon", &self.macaroon)
+            .send()
+            .await
+            .with_context(|| format!("failed to send request to {}", path))?
+            .error_for_status()?
+            .json()
+            .await
+            .with_context(|| format!("failed to deserialize response of {}", path))?;
+
+        Ok(response)
+    }
+
+    async fn post(&self, path: &str, body: serde_json::Value) -> anyhow::Result<()> {
+        self.http
+            .post(&format!("{}{}", self.url, path))
+            .header("Grpc-Metadata-macaroon", &self.macaroon)
+            .json(&body)
+            .send()
+            .await
+            .with_context(|| format!("failed to send request to {}", path))?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    async fn get_info(&self) -> anyhow::Result<GetInfoResponse> {
+        self.get("/v1/getinfo").await
+    }
+
+    async fn new_address(&self) -> anyhow::Result<Address> {
+        let response = self
+            .get::<NewAddressResponse>("/v1/newaddress?type=0")
+            .await?;
+
+        Ok(response.address)
+    }
+
+    async fn confirmed_balance(&self) -> anyhow::Result<u64> {
+        let response = self
+            .get::<WalletBalanceResponse>("/v1/balance/blockchain")
+            .await?;
+
+        if response.confirmed_balance.is_empty() {
+            return Ok(0);
+        }
+
+        response
+            .confirmed_balance
+            .parse()
+            .with_context(|| format!("invalid balance {}", response.confirmed_balance))
+    }
+
+    async fn connect(&self, pubkey: &str, host: &str) -> anyhow::Result<()> {
+        self.post(
+            "/v1/peers",
+            serde_json::json!({ "addr": { "pubkey": pubkey, "host": host } }),
+        )
+        .await
+    }
+
+    async fn open_channel(&self, pubkey: &str, capacity: u64, push: u64) -> anyhow::Result<()> {
+        self.post(
+            "/v1/channels",
+            serde_json::json!({
+                "node_pubkey_string": pubkey,
+                "local_funding_amount": capacity.to_string(),
+                "push_sat": push.to_string(),
+            }),
+        )
+        .await
+    }
+
+    async fn active_channels(&self) -> anyhow::Result<usize> {
+        let response = self.get::<ListChannelsResponse>("/v1/channels").await?;
+
+        Ok(response
+            .channels
+            .iter()
+            .filter(|channel| channel.active)
+            .count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn balance_response_with_defaults_does_deserialize() {
+        let response = serde_json::from_str::<WalletBalanceResponse>("{}").unwrap();
+
+        assert_eq!(response.confirmed_balance, "");
+    }
+
+    #[test]
+    fn channels_response_does_deserialize() {
+        let json = r#"{"channels":[{"active":true,"remote_pubkey":"02ab"},{"active":false}]}"#;
+
+        let response = serde_json::from_str::<ListChannelsResponse>(json).unwrap();
+
+        assert_eq!(response.channels.iter().filter(|c| c.active).count(), 1);
+    }
+}
@@ -1,20 +1,30 @@
-use std::{net::Ipv4Addr, path::Path};
+use std::{
+    io::Read,
+    net::{IpAddr, Ipv4Addr},
+    path::Path,
+};
 
 use anyhow::Context;
 use futures::compat::Future01CompatExt;
 use futures_01::stream::Stream;
 use shiplift::{
-    ContainerOptions, Docker, LogsOptions, NetworkCreateOptions, PullOptions, RmContainerOptions,
+    builder::ContainerOptionsBuilder, ContainerOptions, Docker, LogsOptions, NetworkCreateOptions,
+    PullOptions, RmContainerOptions,
 };
 
 use crate::print_progress;
 
 pub mod bitcoin;
 pub mod cnd;
+pub mod electrs;
 pub mod ethereum;
-mod free_local_port;
+pub(crate) mod free_local_port;
+pub mod lnd;
 
 pub const DOCKER_NETWORK: &str = "create-comit-app";
+/// Resolves to the host from inside a container if it is started with `HOST_GATEWAY`.
+pub const HOST_GATEWAY_NAME: &str = "host.docker.internal";
+const HOST_GATEWAY: &str = "host.docker.internal:host-gateway";
 
 pub struct DockerImage(pub &'static str);
 pub struct LogMessage(pub &'static str);
@@ -106,6 +116,36 @@ pub async fn start(
     Ok(())
 }
 
+/// Reads a single file from a running container.
+pub async fn read_file(container_name: &str, path: &Path) -> anyhow::Result<Vec<u8>> {
+    let archive = Docker::new()
+        .containers()
+        .get(container_name)
+        .copy_from(path)
+        .concat2()
+        .compat()
+        .await
+        .with_context(|| {
+            format!(
+                "failed to copy {} from container {}",
+                path.display(),
+                container_name
+            )
+        })?;
+
+    // docker returns the file wrapped in a tar archive
+    let mut archive = tar::Archive::new(archive.as_slice());
+    let mut entry = archive
+        .entries()?
+        .next()
+        .with_context(|| format!("{} is missing in the archive", path.display()))??;
+
+    let mut content = Vec::new();
+    entry.read_to_end(&mut content)?;
+
+    Ok(content)
+}
+
 pub async fn create_network() -> anyhow::Result<String> {
     let docker = Docker::new();
 
@@ -166,6 +206,97 @@ pub async fn delete_container(name: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Rewrites a URL of a node on the host so that containers can reach it, `localhost` inside a
+/// container is the container itself.
+pub fn container_url(url: &str) -> anyhow::Result<String> {
+    let mut url = reqwest::Url::parse(url).with_context(|| format!("{} is not a URL", url))?;
+
+    let is_loopback = match url.host_str() {
+        Some("localhost") => true,
+        Some(host) => host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+            .map(|ip| ip.is_loopback())
+            .unwrap_or(false),
+        None => false,
+    };
+    if is_loopback {
+        url.set_host(Some(HOST_GATEWAY_NAME))?;
+    }
+
+    Ok(url.to_string().trim_end_matches('/').to_owned())
+}
+
+/// Lets the container reach the host as `HOST_GATEWAY_NAME` if one of the URLs needs it.
+pub fn add_host_gateway(options_builder: &mut ContainerOptionsBuilder, urls: &[&str]) {
+    if urls.iter().any(|url| url.contains(HOST_GATEWAY_NAME)) {
+        options_builder.extra_hosts(vec![HOST_GATEWAY]);
+    }
+}
+
+/// Merges the command-line arguments of a node from `ComitScripts.toml` into its defaults.
+///
+/// `override_args` replace the default with the same option name, `extra_args` are appended and
+/// must not name a default. The `protected` options are needed by comit-scripts to talk to the
+/// node and can be neither overridden nor negated.
+pub fn merge_args(
+    defaults: Vec<String>,
+    protected: &[&str],
+    extra_args: &[String],
+    override_args: &[String],
+) -> anyhow::Result<Vec<String>> {
+    let mut args = defaults;
+
+    for arg in extra_args.iter().chain(override_args) {
+        if let Some(option) = protected.iter().find(|option| refers_to(arg, option)) {
+            anyhow::bail!(
+                "{} cannot be changed, comit-scripts depends on {}",
+                arg,
+                option
+            )
+        }
+    }
+
+    for arg in override_args {
+        let default = args
+            .iter_mut()
+            .find(|default| refers_to(arg, option_name(default)))
+            .with_context(|| {
+                format!(
+                    "{} does not override a default argument, use extra_args",
+                    arg
+                )
+            })?;
+        *default = arg.clone();
+    }
+
+    for arg in extra_args {
+        if args
+            .iter()
+            .any(|default| refers_to(arg, option_name(default)))
+        {
+            anyhow::bail!("{} changes a default argument, use override_args", arg)
+        }
+        args.push(arg.clone());
+    }
+
+    Ok(args)
+}
+
+/// `-rpcbind=0.0.0.0:18443` and `--http.port=8545` name `rpcbind` and `http.port`.
+fn option_name(arg: &str) -> &str {
+    let arg = arg.trim_start_matches('-');
+    arg.split('=').next().unwrap_or(arg)
+}
+
+/// bitcoind negates options with a `no` prefix, e.g. `-notxindex`.
+fn refers_to(arg: &str, option: &str) -> bool {
+    let name = option_name(arg);
+
+    name == option || name.strip_prefix("no") == Some(option)
+}
+
 pub fn docker_daemon_ip() -> anyhow::Result<Ipv4Addr> {
     let socket = match std::env::var("DOCKER_HOST") {
         Ok(host) => parse_ip(host)?,
@@ -199,4 +330,67 @@ mod tests {
 
         assert_eq!(ip, Ipv4Addr::new(192, 168, 99, 100));
     }
+
+    #[test]
+    fn args_are_merged_into_defaults() {
+        let defaults = vec![
+            "-rpcbind=0.0.0.0:18443".to_owned(),
+            "-txindex".to_owned(),
+            "-fallbackfee=0.0002".to_owned(),
+        ];
+
+        let args = merge_args(
+            defaults,
+            &["rpcbind"],
+            &["-maxmempool=50".to_owned()],
+            &["-fallbackfee=0.001".to_owned(), "-notxindex".to_owned()],
+        )
+        .unwrap();
+
+        assert_eq!(
+            args,
+            vec![
+                "-rpcbind=0.0.0.0:18443",
+                "-notxindex",
+                "-fallbackfee=0.001",
+                "-maxmempool=50"
+            ]
+        );
+    }
+
+    #[test]
+    fn args_are_validated() {
+        let defaults = || vec!["-rpcbind=0.0.0.0:18443".to_owned(), "-txindex".to_owned()];
+        let merge = |extra_args: &[&str], override_args: &[&str]| {
+            let to_owned =
+                |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+            merge_args(
+                defaults(),
+                &["rpcbind"],
+                &to_owned(extra_args),
+                &to_owned(override_args),
+            )
+        };
+
+        assert!(merge(&[], &["-rpcbind=127.0.0.1"]).is_err());
+        assert!(merge(&["-norpcbind"], &[]).is_err());
+        assert!(merge(&["-txindex=0"], &[]).is_err());
+        assert!(merge(&[], &["-maxmempool=50"]).is_err());
+    }
+
+    #[test]
+    fn loopback_urls_are_rewritten_for_containers() {
+        assert_eq!(
+            container_url("http://127.0.0.1:18443").unwrap(),
+            "http://host.docker.internal:18443"
+        );
+        assert_eq!(
+            container_url("http://localhost:8545/").unwrap(),
+            "http://host.docker.internal:8545"
+        );
+        assert_eq!(
+            container_url("http://192.168.1.2:8545").unwrap(),
+            "http://192.168.1.2:8545"
+        );
+    }
 }
@@ -0,0 +1,361 @@
+use std::{
+    collections::VecDeque,
+    convert::Infallible,
+    fmt::Display,
+    net::Ipv4Addr,
+    ops::{Add, Sub},
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use futures::lock::Mutex;
+use rust_bitcoin::{util::amount::Denomination, Address as BitcoinAddress, Amount};
+use tokio::net::TcpListener;
+use warp::{http::StatusCode, Filter};
+use web3::types::{Address as EthereumAddress, U256};
+
+use crate::{
+    bitcoind_rpc,
+    config::{self, parse_decimal, ERC20_DECIMALS, ETHER_DECIMALS},
+    docker::{
+        bitcoin::BitcoindComitScriptsHttpWalletEndpoint,
+        ethereum::{self, GethHttpEndpoint},
+    },
+};
+
+/// The faucet listens on this port if it is free.
+pub const PORT: u16 = 8010;
+
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Funding requests are a few hundred bytes, larger bodies are rejected before parsing.
+const MAX_REQUEST_BODY_BYTES: u64 = 16 * 1024;
+
+/// Funds addresses created after the environment started, e.g.
+/// `POST /faucet/btc {"address": "bcrt1...", "amount": "0.5"}`.
+pub struct Faucet {
+    port: u16,
+    bitcoin_wallet: bitcoind_rpc::Client,
+    geth: GethHttpEndpoint,
+    erc20_contract_address: EthereumAddress,
+    limits: config::Faucet,
+    recent_requests: Mutex<VecDeque<Instant>>,
+    paid_out: Mutex<PaidOut>,
+    /// Ethereum transactions are sent one after the other so that nonces don't clash.
+    ethereum_lock: Mutex<()>,
+}
+
+/// Amounts paid out so far, including requests that are still being sent.
+#[derive(Debug)]
+struct PaidOut {
+    btc: Amount,
+    eth: U256,
+    erc20: U256,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Asset {
+    Btc,
+    Eth,
+    Erc20,
+}
+
+impl FromStr for Asset {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "btc" => Ok(Asset::Btc),
+            "eth" => Ok(Asset::Eth),
+            "erc20" => Ok(Asset::Erc20),
+            _ => anyhow::bail!("unknown asset {}", s),
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct FundingRequest {
+    address: String,
+    /// Decimal amount in BTC, ether or tokens.
+    amount: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct FundingResponse {
+    txid: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum Error {
+    #[error("more than {0} requests within a minute, try again later")]
+    RateLimited(u32),
+    #[error("{amount} exceeds the remaining {remaining} of the faucet's total limit")]
+    Exhausted { amount: String, remaining: String },
+    #[error("{0:#}")]
+    InvalidRequest(anyhow::Error),
+    #[error("{0:#}")]
+    Funding(anyhow::Error),
+}
+
+impl Error {
+    fn status(&self) -> StatusCode {
+        match self {
+            Error::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            Error::Exhausted { .. } => StatusCode::FORBIDDEN,
+            Error::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            Error::Funding(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl Faucet {
+    pub fn new(
+        port: u16,
+        bitcoin_wallet: &BitcoindComitScriptsHttpWalletEndpoint,
+        geth: GethHttpEndpoint,
+        erc20_contract_address: EthereumAddress,
+        limits: config::Faucet,
+    ) -> Self {
+        Faucet {
+            port,
+            bitcoin_wallet: bitcoin_wallet.client(),
+            geth,
+            erc20_contract_address,
+            limits,
+            recent_requests: Mutex::new(VecDeque::new()),
+            paid_out: Mutex::new(PaidOut {
+                btc: Amount::ZERO,
+                eth: U256::zero(),
+                erc20: U256::zero(),
+            }),
+            ethereum_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}:{}/faucet", Ipv4Addr::LOCALHOST, self.port)
+    }
+
+    async fn fund(&self, asset: Asset, request: FundingRequest) -> Result<String, Error> {
+        self.check_rate_limit().await?;
+
+        match asset {
+            Asset::Btc => {
+                let address = BitcoinAddress::from_str(&request.address)
+                    .with_context(|| format!("{} is not a valid bitcoin address", request.address))
+                    .map_err(Error::InvalidRequest)?;
+                let amount = Amount::from_str_in(&request.amount, Denomination::Bitcoin)
+                    .with_context(|| format!("{} is not a valid bitcoin amount", request.amount))
+                    .map_err(Error::InvalidRequest)?;
+                check_limit(amount, self.limits.max_btc)?;
+                reserve(
+                    &mut self.paid_out.lock().await.btc,
+                    amount,
+                    self.limits.max_total_btc,
+                )?;
+
+                let txid = self.bitcoin_wallet.send_to_address(&address, amount).await;
+                if txid.is_err() {
+                    release(&mut self.paid_out.lock().await.btc, amount);
+                }
+
+                Ok(txid.map_err(Error::Funding)?.to_string())
+            }
+            Asset::Eth => {
+                let address = parse_ethereum_address(&request.address)?;
+                let amount = parse_decimal(&request.amount, ETHER_DECIMALS)
+                    .map_err(Error::InvalidRequest)?;
+                check_limit(amount, self.limits.max_eth)?;
+                reserve(
+                    &mut self.paid_out.lock().await.eth,
+                    amount,
+                    self.limits.max_total_eth,
+                )?;
+
+                let _guard = self.ethereum_lock.lock().await;
+                let hash = ethereum::fund_address(&self.geth, address, amount).await;
+                if hash.is_err() {
+                    release(&mut self.paid_out.lock().await.eth, amount);
+                }
+
+                Ok(format!("{:#x}", hash.map_err(Error::Funding)?))
+            }
+            Asset::Erc20 => {
+                let address = parse_ethereum_address(&request.address)?;
+                let amount = parse_decimal(&request.amount, usize::from(ERC20_DECIMALS))
+                    .map_err(Error::InvalidRequest)?;
+                check_limit(amount, self.limits.max_erc20)?;
+                reserve(
+                    &mut self.paid_out.lock().await.erc20,
+                    amount,
+                    self.limits.max_total_erc20,
+                )?;
+
+                let _guard = self.ethereum_lock.lock().await;
+                let hash = ethereum::transfer_erc20(
+                    &self.geth,
+                    self.erc20_contract_address,
+                    address,
+                    amount,
+                )
+                .await;
+                if hash.is_err() {
+                    release(&mut self.paid_out.lock().await.erc20, amount);
+                }
+
+                Ok(format!("{:#x}", hash.map_err(Error::Funding)?))
+            }
+        }
+    }
+
+    async fn check_rate_limit(&self) -> Result<(), Error> {
+        let now = Instant::now();
+        let mut recent_requests = self.recent_requests.lock().await;
+
+        while let Some(oldest) = recent_requests.front() {
+            if now.duration_since(*oldest) < RATE_LIMIT_WINDOW {
+                break;
+            }
+            recent_requests.pop_front();
+        }
+
+        if recent_requests.len() >= self.limits.requests_per_minute as usize {
+            return Err(Error::RateLimited(self.limits.requests_per_minute));
+        }
+        recent_requests.push_back(now);
+
+        Ok(())
+    }
+}
+
+fn check_limit<A>(amount: A, max: A) -> Result<(), Error>
+where
+    A: PartialOrd + std::fmt::Display,
+{
+    if amount > max {
+        return Err(Error::InvalidRequest(anyhow::anyhow!(
+            "{} exceeds the maximum of {} per request",
+            amount,
+            max
+        )));
+    }
+
+    Ok(())
+}
+
+/// Adds `amount` to what has been paid out unless that exceeds `max_total`.
+fn reserve<A>(paid_out: &mut A, amount: A, max_total: A) -> Result<(), Error>
+where
+    A: Copy + PartialOrd + Display + Add<Output = A> + Sub<Output = A>,
+{
+    let remaining = max_total - *paid_out;
+    if amount > remaining {
+        return Err(Error::Exhausted {
+            amount: amount.to_string(),
+            remaining: remaining.to_string(),
+        });
+    }
+    *paid_out = *paid_out + amount;
+
+    Ok(())
+}
+
+/// Gives back an amount that was reserved but could not be sent.
+fn release<A>(paid_out: &mut A, amount: A)
+where
+    A: Copy + Sub<Output = A>,
+{
+    *paid_out = *paid_out - amount;
+}
+
+fn parse_ethereum_address(address: &str) -> Result<EthereumAddress, Error> {
+    address
+        .trim_start_matches("0x")
+        .parse()
+        .map_err(|_| Error::InvalidRequest(anyhow::anyhow!("{} is not a valid address", address)))
+}
+
+/// Serves the faucet on the listener bound by start-env until the environment shuts down.
+pub async fn run(faucet: Faucet, mut listener: TcpListener) {
+    let faucet = Arc::new(faucet);
+    let with_faucet = warp::any().map(move || faucet.clone());
+
+    let routes = warp::post()
+        .and(warp::path!("faucet" / Asset))
+        .and(warp::body::content_length_limit(MAX_REQUEST_BODY_BYTES))
+        .and(warp::body::json())
+        .and(with_faucet)
+        .and_then(handle)
+        // lets dapps served from other ports fund their addresses
+        .with(
+            warp::cors()
+                .allow_any_origin()
+                .allow_methods(vec!["POST", "OPTIONS"])
+                .allow_header("content-type"),
+        );
+
+    warp::serve(routes).run_incoming(listener.incoming()).await
+}
+
+async fn handle(
+    asset: Asset,
+    request: FundingRequest,
+    faucet: Arc<Faucet>,
+) -> Result<impl warp::Reply, Infallible> {
+    let reply = match faucet.fund(asset, request).await {
+        Ok(txid) => {
+            warp::reply::with_status(warp::reply::json(&FundingResponse { txid }), StatusCode::OK)
+        }
+        Err(e) => warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error: e.to_string(),
+            }),
+            e.status(),
+        ),
+    };
+
+    Ok(reply)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amounts_above_the_limit_are_rejected() {
+        assert!(check_limit(Amount::ONE_BTC, Amount::ONE_BTC).is_ok());
+        assert!(check_limit(U256::from(2u64), U256::from(1u64)).is_err());
+    }
+
+    #[test]
+    fn total_limit_is_not_exceeded() {
+        let mut paid_out = U256::from(6u64);
+
+        assert!(reserve(&mut paid_out, U256::from(5u64), U256::from(10u64)).is_err());
+        assert_eq!(paid_out, U256::from(6u64));
+
+        assert!(reserve(&mut paid_out, U256::from(4u64), U256::from(10u64)).is_ok());
+        assert_eq!(paid_out, U256::from(10u64));
+
+        release(&mut paid_out, U256::from(4u64));
+        assert_eq!(paid_out, U256::from(6u64));
+    }
+
+    #[test]
+    fn ethereum_addresses_with_and_without_prefix_are_valid() {
+        let address = "0x00a329c0648769a73afac7f9381e08fb43dbea72";
+
+        assert_eq!(
+            parse_ethereum_address(address).unwrap(),
+            parse_ethereum_address(&address[2..]).unwrap()
+        );
+        assert!(parse_ethereum_address("0x1234").is_err());
+    }
+}
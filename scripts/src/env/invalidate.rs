@@ -0,0 +1,79 @@
+use anyhow::Context;
+
+use crate::print_progress;
+
+/// Rolls back the last `blocks` Bitcoin blocks, their transactions return to the mempool.
+///
+/// With `remine`, the same number of alternative blocks is mined right away. Mining is paused
+/// while the blocks are invalidated so that the miner does not race the rollback.
+pub async fn invalidate_blocks(blocks: u32, remine: bool) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        blocks > 0,
+        "number of blocks to invalidate must be at least 1"
+    );
+    super::ensure_running().await?;
+
+    let was_paused = super::miner::is_paused().await?;
+    super::miner::set_paused(true).await?;
+
+    let result = invalidate_and_remine(blocks, remine).await;
+
+    super::miner::set_paused(was_paused).await?;
+
+    result
+}
+
+async fn invalidate_and_remine(blocks: u32, remine: bool) -> anyhow::Result<()> {
+    let node = super::bitcoind_node(0)?;
+
+    let height = node.get_blockchain_info().await?.blocks;
+    anyhow::ensure!(
+        u64::from(blocks) <= height,
+        "cannot invalidate {} blocks, the chain only has {}",
+        blocks,
+        height
+    );
+
+    print_progress!("Invalidating the last {} Bitcoin blocks", blocks);
+
+    let first_invalid = node.get_block_hash(height - u64::from(blocks) + 1).await?;
+    node.invalidate_block(&first_invalid)
+        .await
+        .with_context(|| format!("failed to invalidate block {}", first_invalid))?;
+
+    println!("✓");
+
+    if remine {
+        super::mine_bitcoin(blocks, 0).await?;
+    }
+
+    Ok(())
+}
+
+/// Reconsiders all blocks invalidated with `invalidate-blocks`.
+///
+/// The original chain only becomes active again if it has more work than the current one.
+pub async fn reconsider_blocks() -> anyhow::Result<()> {
+    super::ensure_running().await?;
+
+    let node = super::bitcoind_node(0)?;
+
+    print_progress!("Reconsidering invalidated Bitcoin blocks");
+
+    let invalid_tips = node
+        .get_chain_tips()
+        .await?
+        .into_iter()
+        .filter(|tip| tip.status == "invalid");
+
+    // Reconsidering a tip also clears the invalid status of its ancestors.
+    for tip in invalid_tips {
+        node.reconsider_block(&tip.hash)
+            .await
+            .with_context(|| format!("failed to reconsider block {}", tip.hash))?;
+    }
+
+    println!("✓");
+
+    Ok(())
+}
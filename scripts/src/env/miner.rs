@@ -0,0 +1,117 @@
+use std::{io::ErrorKind, time::Duration};
+
+use anyhow::Context;
+use tokio::time::delay_for;
+
+use crate::{
+    bitcoind_rpc,
+    config::{self, MiningMode},
+    docker::bitcoin::{self, BitcoindComitScriptsHttpWalletEndpoint},
+    print_progress, temp_fs,
+};
+
+const MEMPOOL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+pub async fn run(endpoint: BitcoindComitScriptsHttpWalletEndpoint, config: config::Mining) {
+    let wallet = endpoint.client();
+
+    let poll_interval = match config.mode {
+        MiningMode::Manual => return,
+        MiningMode::Interval => Duration::from_secs(config.block_interval),
+        MiningMode::OnMempool => MEMPOOL_POLL_INTERVAL,
+    };
+
+    loop {
+        delay_for(poll_interval).await;
+
+        if let Err(e) = mine(&wallet, &config).await {
+            eprintln!("Failed to mine bitcoin: {:#}", e);
+        }
+    }
+}
+
+/// Mines a block unless mining is paused or, in `on_mempool` mode, the mempool is too small.
+async fn mine(wallet: &bitcoind_rpc::Client, config: &config::Mining) -> anyhow::Result<()> {
+    if is_paused().await? {
+        return Ok(());
+    }
+
+    if config.mode == MiningMode::OnMempool {
+        let mempool = wallet.get_mempool_info().await?;
+        if mempool.size == 0 || mempool.size < config.min_mempool_transactions {
+            return Ok(());
+        }
+
+        delay_for(Duration::from_secs(config.mempool_delay)).await;
+
+        // mining may have been paused while waiting
+        if is_paused().await? {
+            return Ok(());
+        }
+    }
+
+    super::time::sync_bitcoin_clocks().await?;
+    bitcoin::mine_a_block(wallet).await
+}
+
+pub(super) async fn is_paused() -> anyhow::Result<bool> {
+    let path = temp_fs::mining_paused_file_path()?;
+
+    Ok(tokio::fs::metadata(path).await.is_ok())
+}
+
+pub(super) async fn set_paused(paused: bool) -> anyhow::Result<()> {
+    let path = temp_fs::mining_paused_file_path()?;
+
+    if paused {
+        tokio::fs::File::create(&path)
+            .await
+            .with_context(|| format!("failed to create file {}", path.display()))?;
+    } else {
+        match tokio::fs::remove_file(&path).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => {
+                return Err(e).with_context(|| format!("failed to remove file {}", path.display()))
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Mines blocks on the given node, the rewards go to the comit-scripts wallet.
+pub async fn mine_bitcoin(blocks: u32, node: u32) -> anyhow::Result<()> {
+    let wallet = super::comit_scripts_wallet()?;
+    let node_client = super::bitcoind_node(node)?;
+
+    print_progress!("Mining {} Bitcoin blocks on node {}", blocks, node);
+
+    super::time::sync_bitcoin_clocks().await?;
+    let address = wallet.get_new_address("bech32").await?;
+    node_client
+        .generate_to_address(blocks, &address)
+        .await
+        .context("failed to generate blocks")?;
+
+    println!("✓");
+
+    Ok(())
+}
+
+pub async fn pause_mining() -> anyhow::Result<()> {
+    super::ensure_running().await?;
+    set_paused(true).await?;
+
+    println!("Bitcoin mining paused.");
+
+    Ok(())
+}
+
+pub async fn resume_mining() -> anyhow::Result<()> {
+    super::ensure_running().await?;
+    set_paused(false).await?;
+
+    println!("Bitcoin mining resumed.");
+
+    Ok(())
+}
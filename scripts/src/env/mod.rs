@@ -1,29 +1,43 @@
-use std::time::Duration;
-
+use anyhow::Context;
+use envfile::EnvFile;
 use futures::{
     future::{try_select, Either},
     pin_mut,
 };
-use tokio::time::delay_for;
 
 use crate::{
+    bitcoind_rpc,
     docker::{
-        bitcoin::{self, BitcoindComitScriptsHttpWalletEndpoint},
-        delete_container, delete_network,
+        bitcoin::{self, COMIT_SCRIPTS_WALLET_NAME},
+        delete_container, delete_network, electrs, ethereum,
     },
-    print_progress,
+    env_vars, print_progress, temp_fs,
 };
 
+mod faucet;
+mod invalidate;
+mod miner;
+mod partition;
 mod start;
+mod time;
+mod traffic;
+
+pub use self::{
+    invalidate::{invalidate_blocks, reconsider_blocks},
+    miner::{mine_bitcoin, pause_mining, resume_mining},
+    partition::{connect_nodes, disconnect_nodes, reorg},
+    time::advance_time,
+    traffic::set_congestion,
+};
 
-pub async fn start() {
+pub async fn start(seed: Option<String>) {
     if crate::temp_fs::dir_exist().await {
         eprintln!("It seems that `start-env` is already running.\nIf it is not the case, run `yarn comit-scripts force-clean-env` and try again.");
         ::std::process::exit(1);
     }
 
     let ctrl_c = tokio::signal::ctrl_c();
-    let start_env = self::start::execute();
+    let start_env = self::start::execute(seed);
 
     pin_mut!(start_env);
     pin_mut!(ctrl_c);
@@ -31,8 +45,23 @@ pub async fn start() {
     let result = try_select(start_env, ctrl_c).await;
 
     match result {
-        Ok(Either::Left((self::start::Environment { bitcoind, .. }, ctrl_c))) => {
-            tokio::spawn(new_miner(bitcoind.comit_scripts_wallet_endpoint));
+        Ok(Either::Left((
+            self::start::Environment {
+                bitcoind,
+                mining,
+                fee_market,
+                faucet,
+                ..
+            },
+            ctrl_c,
+        ))) => {
+            tokio::spawn(miner::run(bitcoind.comit_scripts_wallet_endpoint, mining));
+            if fee_market.enabled {
+                tokio::spawn(traffic::run(bitcoind.http_endpoint, fee_market));
+            }
+            if let Some((faucet, listener)) = faucet {
+                tokio::spawn(faucet::run(faucet, listener));
+            }
 
             let _ = ctrl_c.await;
         }
@@ -47,21 +76,77 @@ pub async fn start() {
     println!("✓");
 }
 
-async fn new_miner(endpoint: BitcoindComitScriptsHttpWalletEndpoint) -> anyhow::Result<()> {
-    loop {
-        delay_for(Duration::from_secs(1)).await;
-        bitcoin::mine_a_block(&endpoint.to_string()).await?;
-    }
-}
-
 pub async fn clean_up() {
-    let _ = delete_container("bitcoin").await;
-    let _ = delete_container("ethereum").await;
+    let _ = delete_container(bitcoin::CONTAINER_NAME).await;
+    let _ = delete_container(bitcoin::SECOND_NODE_CONTAINER_NAME).await;
+    let _ = delete_container(ethereum::CONTAINER_NAME).await;
     let _ = delete_container("cnd_0").await;
     let _ = delete_container("cnd_1").await;
+    let _ = delete_container("lnd_0").await;
+    let _ = delete_container("lnd_1").await;
+    let _ = delete_container(electrs::CONTAINER_NAME).await;
     let _ = delete_network().await;
 
     if let Ok(path) = crate::temp_fs::dir_path() {
         let _ = tokio::fs::remove_dir_all(path).await;
     }
 }
+
+async fn ensure_running() -> anyhow::Result<()> {
+    if !temp_fs::dir_exist().await {
+        anyhow::bail!(
+            "No running environment found, start one with `yarn comit-scripts start-env`."
+        )
+    }
+
+    Ok(())
+}
+
+/// Connects to the wallet comit-scripts uses for funding and mining in the running environment.
+fn comit_scripts_wallet() -> anyhow::Result<bitcoind_rpc::Client> {
+    Ok(bitcoind_node(0)?.with_wallet(COMIT_SCRIPTS_WALLET_NAME))
+}
+
+/// Connects to a bitcoind of the running environment, `0` is the first and `1` the second node.
+fn bitcoind_node(node: u32) -> anyhow::Result<bitcoind_rpc::Client> {
+    let envfile = EnvFile::new(temp_fs::env_file_path()?)
+        .context("failed to read env file, is `start-env` running?")?;
+    let get = |key: &str| {
+        envfile
+            .get(key)
+            .with_context(|| format!("{} is missing in the env file", key))
+    };
+
+    let url = match node {
+        0 => get(env_vars::BITCOIN_HTTP_URI)?,
+        1 => get(env_vars::BITCOIN_HTTP_URI_1)
+            .context("the second Bitcoin node is not running, enable it with `second_node`")?,
+        node => anyhow::bail!("Bitcoin node {} does not exist", node),
+    };
+
+    Ok(bitcoind_rpc::Client::new(
+        url,
+        get(env_vars::BITCOIN_USERNAME)?,
+        get(env_vars::BITCOIN_PASSWORD)?,
+    ))
+}
+
+/// Connects to all bitcoinds of the running environment.
+fn bitcoind_nodes() -> anyhow::Result<Vec<bitcoind_rpc::Client>> {
+    let mut nodes = vec![bitcoind_node(0)?];
+    if let Ok(node) = bitcoind_node(1) {
+        nodes.push(node);
+    }
+
+    Ok(nodes)
+}
+
+fn ethereum_node_url() -> anyhow::Result<String> {
+    let envfile = EnvFile::new(temp_fs::env_file_path()?)
+        .context("failed to read env file, is `start-env` running?")?;
+    let url = envfile
+        .get(env_vars::ETHEREUM_NODE_HTTP_URL)
+        .context("ETHEREUM_NODE_HTTP_URL is missing in the env file")?;
+
+    Ok(url.to_owned())
+}
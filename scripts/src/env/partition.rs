@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use tokio::time::delay_for;
+
+use crate::{bitcoind_rpc, docker::bitcoin::FIRST_NODE_PEER_ADDRESS, print_progress};
+
+const SYNC_POLL_INTERVAL: Duration = Duration::from_millis(200);
+const SYNC_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Disconnects the second Bitcoin node from the first one so that both can mine their own chain.
+pub async fn disconnect_nodes() -> anyhow::Result<()> {
+    super::ensure_running().await?;
+
+    print_progress!("Disconnecting Bitcoin nodes");
+    set_connected(&super::bitcoind_node(1)?, false).await?;
+    println!("✓");
+
+    Ok(())
+}
+
+/// Reconnects the Bitcoin nodes, the node with the shorter chain reorganises to the longer one.
+pub async fn connect_nodes() -> anyhow::Result<()> {
+    super::ensure_running().await?;
+
+    print_progress!("Connecting Bitcoin nodes");
+    set_connected(&super::bitcoind_node(1)?, true).await?;
+    wait_until_synced(&super::bitcoind_node(0)?, &super::bitcoind_node(1)?).await?;
+    println!("✓");
+
+    Ok(())
+}
+
+/// Forces a reorganisation of the given depth on the first Bitcoin node.
+///
+/// The nodes are disconnected, the first node mines `depth` blocks and the second node mines a
+/// competing chain of `depth + 1` blocks which the first node switches to once they are
+/// reconnected. Mining is paused for the duration of the reorg.
+pub async fn reorg(depth: u32) -> anyhow::Result<()> {
+    anyhow::ensure!(depth > 0, "reorg depth must be at least 1");
+    super::ensure_running().await?;
+
+    let node_0 = super::bitcoind_node(0)?;
+    let node_1 = super::bitcoind_node(1)?;
+
+    let was_paused = super::miner::is_paused().await?;
+    super::miner::set_paused(true).await?;
+
+    let result = force_reorg(&node_0, &node_1, depth).await;
+
+    super::miner::set_paused(was_paused).await?;
+
+    result
+}
+
+async fn force_reorg(
+    node_0: &bitcoind_rpc::Client,
+    node_1: &bitcoind_rpc::Client,
+    depth: u32,
+) -> anyhow::Result<()> {
+    let address = super::comit_scripts_wallet()?
+        .get_new_address("bech32")
+        .await?;
+
+    print_progress!("Forcing a Bitcoin reorg of depth {}", depth);
+
+    wait_until_synced(node_0, node_1).await?;
+    set_connected(node_1, false).await?;
+
+    node_0
+        .generate_to_address(depth, &address)
+        .await
+        .context("failed to mine blocks on the first node")?;
+    node_1
+        .generate_to_address(depth + 1, &address)
+        .await
+        .context("failed to mine blocks on the second node")?;
+
+    set_connected(node_1, true).await?;
+    wait_until_synced(node_0, node_1).await?;
+
+    println!("✓");
+
+    Ok(())
+}
+
+async fn set_connected(node: &bitcoind_rpc::Client, connected: bool) -> anyhow::Result<()> {
+    node.set_network_active(connected).await?;
+
+    if connected {
+        node.add_node(FIRST_NODE_PEER_ADDRESS, "onetry").await?;
+    }
+
+    Ok(())
+}
+
+async fn wait_until_synced(
+    node_0: &bitcoind_rpc::Client,
+    node_1: &bitcoind_rpc::Client,
+) -> anyhow::Result<()> {
+    let sync = async {
+        loop {
+            let info_0 = node_0.get_blockchain_info().await?;
+            let info_1 = node_1.get_blockchain_info().await?;
+
+            if info_0.best_block_hash == info_1.best_block_hash {
+                return Ok::<_, anyhow::Error>(());
+            }
+
+            delay_for(SYNC_POLL_INTERVAL).await;
+        }
+    };
+
+    tokio::time::timeout(SYNC_TIMEOUT, sync)
+        .await
+        .context("Bitcoin nodes did not sync in time")?
+}
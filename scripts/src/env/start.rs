@@ -5,13 +5,20 @@ use crate::{
     config::{self, Config},
     docker::{
         self,
-        bitcoin::{self, BitcoindInstance, PASSWORD, USERNAME},
+        bitcoin::{self, BitcoindInstance},
         cnd::{self, CndInstance},
+        electrs::{self, ElectrsInstance},
         ethereum::{self, GethInstance},
+        free_local_port::bind_preferred_or_free_local_port,
+        lnd::{self, LndInstance},
     },
-    print_progress, temp_fs,
+    env::faucet::{self, Faucet},
+    env_vars, print_progress,
+    seed::Seed,
+    temp_fs,
 };
 use std::path::Path;
+use tokio::net::TcpListener;
 
 pub struct Environment {
     pub docker_network_id: String,
@@ -19,9 +26,14 @@ pub struct Environment {
     pub geth: GethInstance,
     pub cnd_0: CndInstance,
     pub cnd_1: CndInstance,
+    pub lnds: Option<(LndInstance, LndInstance)>,
+    pub electrs: Option<ElectrsInstance>,
+    pub mining: config::Mining,
+    pub fee_market: config::FeeMarket,
+    pub faucet: Option<(Faucet, TcpListener)>,
 }
 
-pub async fn execute() -> anyhow::Result<Environment> {
+pub async fn execute(seed: Option<String>) -> anyhow::Result<Environment> {
     print_progress!("Creating Docker network (create-comit-app)");
 
     let docker_network_id = docker::create_network().await?;
@@ -31,61 +43,298 @@ pub async fn execute() -> anyhow::Result<Environment> {
     print_progress!("Reading config file");
 
     let path = std::env::current_dir()?.join(Path::new(config::FILE_NAME));
-    let (bitcoin_config, ethereum_config) = match Config::from_file(&path) {
-        Ok(config) => {
+    let config = match Config::from_file(&path)? {
+        Some(config) => {
             println!("✓");
-            (config.ethereum, config.bitcoin)
+            config
         }
-        Err(_e) => {
+        None => {
             println!("No config file found, only funding generated default accounts.");
-            (None, None)
+            Config::default()
         }
     };
 
+    let funding_confirmations = config.funding_confirmations();
+
+    // The seed given on the command line takes precedence over the one in the config file
+    let seed = seed
+        .or(config.seed)
+        .map(Seed::new)
+        .unwrap_or_else(Seed::random);
+
     print_progress!("Starting Ethereum node");
 
-    let geth = ethereum::new_geth_instance(bitcoin_config).await?;
+    let geth = ethereum::new_geth_instance(config.ethereum, &seed).await?;
 
     println!("✓");
 
     print_progress!("Starting Bitcoin node");
 
-    let bitcoind = bitcoin::new_bitcoind_instance(ethereum_config).await?;
+    let mining = config
+        .bitcoin
+        .as_ref()
+        .map(|config| config.mining)
+        .unwrap_or_default();
+    let fee_market = config
+        .bitcoin
+        .as_ref()
+        .map(|config| config.fee_market)
+        .unwrap_or_default();
+    let lightning = config.bitcoin.as_ref().and_then(|config| config.lightning);
+    let start_electrs = config
+        .bitcoin
+        .as_ref()
+        .map(|config| config.electrs)
+        .unwrap_or_default();
+    let bitcoind = bitcoin::new_bitcoind_instance(config.bitcoin, &seed).await?;
 
     println!("✓");
 
+    let electrs = if start_electrs {
+        print_progress!("Starting electrs");
+        let electrs = electrs::new_instance(&bitcoind.http_endpoint).await?;
+        println!("✓");
+
+        Some(electrs)
+    } else {
+        None
+    };
+
+    let lnds = match lightning {
+        Some(lightning) => {
+            print_progress!("Starting two lnds");
+            let lnds = lnd::new_instances(
+                lightning,
+                &bitcoind.credentials,
+                &bitcoind.comit_scripts_wallet_endpoint.client(),
+            )
+            .await?;
+            println!("✓");
+
+            Some(lnds)
+        }
+        None => None,
+    };
+
     print_progress!("Starting two cnds");
-    let cnd_0 = cnd::new_instance(0)
-        .await
-        .context("failed to start first cnd")?;
+    let cnd_0 = cnd::new_instance(
+        0,
+        &bitcoind.cnd_node_url,
+        &geth.cnd_node_url,
+        lnds.as_ref().map(|lnds| &lnds.0),
+    )
+    .await
+    .context("failed to start first cnd")?;
 
-    let cnd_1 = cnd::new_instance(1)
-        .await
-        .context("failed to start second cnd")?;
+    let cnd_1 = cnd::new_instance(
+        1,
+        &bitcoind.cnd_node_url,
+        &geth.cnd_node_url,
+        lnds.as_ref().map(|lnds| &lnds.1),
+    )
+    .await
+    .context("failed to start second cnd")?;
 
     println!("✓");
 
+    let faucet_config = config.faucet.unwrap_or_default();
+    let faucet = if faucet_config.enabled {
+        let listener = bind_preferred_or_free_local_port(faucet::PORT)
+            .await
+            .context("unable to bind the faucet")?;
+        let faucet = Faucet::new(
+            listener.local_addr()?.port(),
+            &bitcoind.comit_scripts_wallet_endpoint,
+            geth.http_endpoint.clone(),
+            geth.erc20_contract_address,
+            faucet_config,
+        );
+
+        Some((faucet, listener))
+    } else {
+        None
+    };
+
+    print_progress!(
+        "Waiting for {} confirmations of the funding transactions",
+        funding_confirmations
+    );
+    bitcoin::confirm_transactions(
+        &bitcoind.comit_scripts_wallet_endpoint.client(),
+        &bitcoind.funding_txids,
+        funding_confirmations,
+    )
+    .await
+    .context("failed to confirm Bitcoin funding transactions")?;
+    ethereum::wait_for_confirmations(
+        &geth.http_endpoint,
+        &geth.funding_transactions,
+        funding_confirmations,
+    )
+    .await
+    .context("failed to confirm Ethereum funding transactions")?;
+    println!("✓");
+
     let env_file_str = temp_fs::create_env_file().await?;
     print_progress!("Writing configuration to {}", env_file_str);
 
     let mut envfile = EnvFile::new(env_file_str)?;
-    envfile.update("ETHEREUM_KEY_0", &format!("{}", geth.account_0.private_key));
-    envfile.update("ETHEREUM_KEY_1", &format!("{}", geth.account_1.private_key));
+    envfile.update(env_vars::ENVIRONMENT_SEED, &seed.to_string());
+    envfile.update(
+        &env_vars::per_actor(env_vars::ETHEREUM_KEY, 0),
+        &format!("{}", geth.account_0.private_key),
+    );
     envfile.update(
-        "ERC20_CONTRACT_ADDRESS",
+        &env_vars::per_actor(env_vars::ETHEREUM_KEY, 1),
+        &format!("{}", geth.account_1.private_key),
+    );
+    envfile.update(
+        env_vars::ERC20_CONTRACT_ADDRESS,
         &format!("{:#x}", geth.erc20_contract_address),
     );
-    envfile.update("ETHEREUM_NODE_HTTP_URL", &geth.http_endpoint.to_string());
+    for token in &geth.tokens {
+        envfile.update(
+            &env_vars::token_contract_address(&token.symbol),
+            &format!("{:#x}", token.address),
+        );
+    }
+    for contract in &geth.contracts {
+        envfile.update(&contract.env, &format!("{:#x}", contract.address));
+    }
+    envfile.update(
+        env_vars::ETHEREUM_NODE_HTTP_URL,
+        &geth.http_endpoint.to_string(),
+    );
+    envfile.update(
+        env_vars::ETHEREUM_FUNDING_TXIDS,
+        &geth
+            .funding_transactions
+            .iter()
+            .map(|transaction| format!("{:#x}", transaction))
+            .collect::<Vec<_>>()
+            .join(","),
+    );
+
+    envfile.update(
+        &env_vars::per_actor(env_vars::MNEMONIC, 0),
+        seed.mnemonic(0)?.phrase(),
+    );
+    envfile.update(
+        &env_vars::per_actor(env_vars::MNEMONIC, 1),
+        seed.mnemonic(1)?.phrase(),
+    );
 
-    envfile.update("BITCOIN_WALLET_0", &bitcoind.account_0.to_string());
-    envfile.update("BITCOIN_WALLET_1", &bitcoind.account_1.to_string());
-    envfile.update("BITCOIN_P2P_URI", &bitcoind.p2p_uri.to_string());
-    envfile.update("BITCOIN_HTTP_URI", &bitcoind.http_endpoint.to_string());
-    envfile.update("BITCOIN_USERNAME", USERNAME);
-    envfile.update("BITCOIN_PASSWORD", PASSWORD);
+    envfile.update(
+        &env_vars::per_actor(env_vars::BITCOIN_WALLET, 0),
+        &bitcoind.account_0.to_string(),
+    );
+    envfile.update(
+        &env_vars::per_actor(env_vars::BITCOIN_WALLET, 1),
+        &bitcoind.account_1.to_string(),
+    );
+    if let Some(endpoint) = &bitcoind.account_0_wallet_endpoint {
+        envfile.update(
+            &env_vars::per_actor(env_vars::BITCOIN_WALLET_URL, 0),
+            &endpoint.to_string(),
+        );
+    }
+    if let Some(endpoint) = &bitcoind.account_1_wallet_endpoint {
+        envfile.update(
+            &env_vars::per_actor(env_vars::BITCOIN_WALLET_URL, 1),
+            &endpoint.to_string(),
+        );
+    }
+    envfile.update(
+        env_vars::BITCOIN_FUNDING_TXIDS,
+        &bitcoind
+            .funding_txids
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(","),
+    );
+    if let Some(p2p_uri) = &bitcoind.p2p_uri {
+        envfile.update(env_vars::BITCOIN_P2P_URI, &p2p_uri.to_string());
+    }
+    envfile.update(
+        env_vars::BITCOIN_HTTP_URI,
+        &bitcoind.http_endpoint.to_string(),
+    );
+    if let Some(zmq_endpoints) = &bitcoind.zmq_endpoints {
+        envfile.update(
+            env_vars::BITCOIN_ZMQ_RAWBLOCK,
+            &zmq_endpoints.raw_block.to_string(),
+        );
+        envfile.update(
+            env_vars::BITCOIN_ZMQ_RAWTX,
+            &zmq_endpoints.raw_tx.to_string(),
+        );
+        envfile.update(
+            env_vars::BITCOIN_ZMQ_HASHBLOCK,
+            &zmq_endpoints.hash_block.to_string(),
+        );
+        envfile.update(
+            env_vars::BITCOIN_ZMQ_HASHTX,
+            &zmq_endpoints.hash_tx.to_string(),
+        );
+    }
+    if let Some(endpoint) = &bitcoind.second_node_http_endpoint {
+        envfile.update(env_vars::BITCOIN_HTTP_URI_1, &endpoint.to_string());
+    }
+    envfile.update(env_vars::BITCOIN_USERNAME, &bitcoind.credentials.username);
+    envfile.update(env_vars::BITCOIN_PASSWORD, &bitcoind.credentials.password);
+
+    envfile.update(
+        &env_vars::per_actor(env_vars::HTTP_URL_CND, 0),
+        &cnd_0.http_endpoint.to_string(),
+    );
+    envfile.update(
+        &env_vars::per_actor(env_vars::HTTP_URL_CND, 1),
+        &cnd_1.http_endpoint.to_string(),
+    );
+
+    if let Some(electrs) = &electrs {
+        envfile.update(
+            env_vars::ELECTRUM_URI,
+            &electrs.electrum_endpoint.to_string(),
+        );
+        envfile.update(
+            env_vars::ESPLORA_HTTP_URL,
+            &electrs.esplora_endpoint.to_string(),
+        );
+    }
+
+    if let Some((lnd_0, lnd_1)) = &lnds {
+        for (index, lnd) in [lnd_0, lnd_1].iter().enumerate() {
+            let tls_cert_path = temp_fs::dir_path()?.join(format!("lnd_{}_tls.cert", index));
+            tokio::fs::write(&tls_cert_path, &lnd.tls_cert).await?;
+
+            envfile.update(
+                &env_vars::per_actor(env_vars::LND_REST_URL, index),
+                &lnd.rest_endpoint.to_string(),
+            );
+            envfile.update(
+                &env_vars::per_actor(env_vars::LND_GRPC_URI, index),
+                &lnd.grpc_endpoint.to_string(),
+            );
+            envfile.update(
+                &env_vars::per_actor(env_vars::LND_PUBKEY, index),
+                &lnd.identity_pubkey,
+            );
+            envfile.update(
+                &env_vars::per_actor(env_vars::LND_MACAROON, index),
+                &hex::encode(&lnd.admin_macaroon),
+            );
+            envfile.update(
+                &env_vars::per_actor(env_vars::LND_TLS_CERT_PATH, index),
+                &tls_cert_path.display().to_string(),
+            );
+        }
+    }
 
-    envfile.update("HTTP_URL_CND_0", &cnd_0.http_endpoint.to_string());
-    envfile.update("HTTP_URL_CND_1", &cnd_1.http_endpoint.to_string());
+    if let Some((faucet, _)) = &faucet {
+        envfile.update(env_vars::FAUCET_URL, &faucet.url());
+    }
 
     envfile.write()?;
 
@@ -98,5 +347,10 @@ pub async fn execute() -> anyhow::Result<Environment> {
         bitcoind,
         cnd_0,
         cnd_1,
+        lnds,
+        electrs,
+        mining,
+        fee_market,
+        faucet,
     })
 }
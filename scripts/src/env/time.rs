@@ -0,0 +1,193 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+
+use crate::{print_progress, temp_fs};
+
+/// Median-time-past is the median timestamp of this many blocks.
+const MEDIAN_TIME_SPAN: u32 = 11;
+
+/// Moves the clocks of both ledgers forward, e.g. to reach the expiry of an HTLC.
+///
+/// Bitcoin: all bitcoinds get a mock time and enough blocks are mined for median-time-past to
+/// catch up. Ethereum: the node needs to support `evm_increaseTime` which geth does not.
+pub async fn advance_time(duration: &str) -> anyhow::Result<()> {
+    super::ensure_running().await?;
+    let seconds = parse_duration(duration)?;
+
+    // everything that can fail is checked before Ethereum time moves as it cannot be moved back
+    let offset = offset()
+        .await?
+        .unwrap_or(0)
+        .checked_add(seconds)
+        .context("the time offset overflows")?;
+    let path = temp_fs::time_offset_file_path()?;
+    let wallet = super::comit_scripts_wallet()?;
+    let node = super::bitcoind_node(0)?;
+
+    // Ethereum goes first because it fails if the node does not support it
+    print_progress!("Advancing Ethereum time by {} seconds", seconds);
+    increase_ethereum_time(seconds).await?;
+    println!("✓");
+
+    print_progress!("Advancing Bitcoin time by {} seconds", seconds);
+    tokio::fs::write(&path, offset.to_string())
+        .await
+        .with_context(|| format!("failed to write file {}", path.display()))?;
+
+    sync_bitcoin_clocks().await?;
+
+    let address = wallet.get_new_address("bech32").await?;
+    node.generate_to_address(MEDIAN_TIME_SPAN, &address)
+        .await
+        .context("failed to generate blocks")?;
+    println!("✓");
+
+    Ok(())
+}
+
+/// Sets the clocks of all bitcoinds to the system time plus the offset of all `advance-time`
+/// calls so far. Needs to be called before mining as a mock time does not move on its own.
+pub(super) async fn sync_bitcoin_clocks() -> anyhow::Result<()> {
+    let offset = match offset().await? {
+        Some(offset) => offset,
+        None => return Ok(()),
+    };
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .context("system time is before the UNIX epoch")?
+        .as_secs();
+    let mock_time = now
+        .checked_add(offset)
+        .context("the time offset overflows")?;
+
+    // the nodes reject blocks from a peer which are too far in the future of their own clock
+    for node in super::bitcoind_nodes()? {
+        node.set_mock_time(mock_time).await?;
+    }
+
+    Ok(())
+}
+
+async fn offset() -> anyhow::Result<Option<u64>> {
+    let path = temp_fs::time_offset_file_path()?;
+
+    match tokio::fs::read_to_string(&path).await {
+        Ok(offset) => offset
+            .trim()
+            .parse()
+            .map(Some)
+            .with_context(|| format!("invalid time offset in {}", path.display())),
+        Err(_) => Ok(None),
+    }
+}
+
+async fn increase_ethereum_time(seconds: u64) -> anyhow::Result<()> {
+    let url = super::ethereum_node_url()?;
+
+    ethereum_rpc(&url, "evm_increaseTime", serde_json::json!([seconds]))
+        .await
+        .context("the Ethereum node does not support advancing time, set `node = \"ganache\"` in the `[ethereum]` section of ComitScripts.toml")?;
+    // the new time only applies to blocks mined afterwards
+    ethereum_rpc(&url, "evm_mine", serde_json::json!([])).await?;
+
+    Ok(())
+}
+
+async fn ethereum_rpc(url: &str, method: &str, params: serde_json::Value) -> anyhow::Result<()> {
+    #[derive(serde::Deserialize)]
+    struct Response {
+        error: Option<serde_json::Value>,
+    }
+
+    let response = reqwest::Client::new()
+        .post(url)
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        }))
+        .send()
+        .await?
+        .json::<Response>()
+        .await?;
+
+    match response.error {
+        Some(error) => anyhow::bail!("{} failed: {}", method, error),
+        None => Ok(()),
+    }
+}
+
+/// Parses durations like `90`, `90s`, `30m`, `2h`, `1d` or `1h30m` into seconds.
+fn parse_duration(duration: &str) -> anyhow::Result<u64> {
+    let invalid = || {
+        anyhow::anyhow!(
+            "invalid duration {}, expected e.g. 90s, 30m, 2h or 1d",
+            duration
+        )
+    };
+
+    let mut seconds = 0u64;
+    let mut number = String::new();
+
+    for c in duration.trim().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        let value = number.parse::<u64>().map_err(|_| invalid())?;
+        seconds = value
+            .checked_mul(unit)
+            .and_then(|value| seconds.checked_add(value))
+            .ok_or_else(invalid)?;
+        number.clear();
+    }
+
+    // a trailing number without unit are seconds
+    if !number.is_empty() {
+        let value = number.parse::<u64>().map_err(|_| invalid())?;
+        seconds = seconds.checked_add(value).ok_or_else(invalid)?;
+    }
+
+    if seconds == 0 {
+        return Err(invalid());
+    }
+
+    Ok(seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("90").unwrap(), 90);
+        assert_eq!(parse_duration("90s").unwrap(), 90);
+        assert_eq!(parse_duration("30m").unwrap(), 1800);
+        assert_eq!(parse_duration("2h").unwrap(), 7200);
+        assert_eq!(parse_duration("1d").unwrap(), 86400);
+        assert_eq!(parse_duration("1h30m").unwrap(), 5400);
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("0s").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("1y").is_err());
+        assert!(parse_duration("-1h").is_err());
+        assert!(parse_duration("18446744073709551615d").is_err());
+        assert!(parse_duration("18446744073709551615s1s").is_err());
+    }
+}
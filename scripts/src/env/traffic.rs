@@ -0,0 +1,156 @@
+use std::{convert::TryFrom, time::Duration};
+
+use anyhow::Context;
+use rust_bitcoin::Amount;
+use secp256k1::rand::{thread_rng, Rng};
+use tokio::time::delay_for;
+
+use crate::{
+    bitcoind_rpc::{self, Request},
+    config,
+    docker::bitcoin::{BitcoindHttpEndpoint, TRAFFIC_WALLET_NAME},
+    temp_fs,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Virtual size of a transaction with one P2WPKH input and two P2WPKH outputs.
+const TRANSACTION_VSIZE: u64 = 141;
+/// Upper bound of transactions sent per poll so that a single batch stays small.
+const MAX_TRANSACTIONS_PER_POLL: u64 = 50;
+const PAYMENT_SATS: u64 = 100_000;
+
+/// Keeps the mempool filled with transactions at random fee rates between the configured bounds.
+///
+/// The wallet pays itself so that its balance only goes down by the fees. Errors are logged and
+/// the next poll tries again so that a single failure doesn't stop the traffic.
+pub async fn run(endpoint: BitcoindHttpEndpoint, config: config::FeeMarket) {
+    let wallet = endpoint.client().with_wallet(TRAFFIC_WALLET_NAME);
+
+    loop {
+        delay_for(POLL_INTERVAL).await;
+
+        if let Err(e) = top_up_mempool(&wallet, &config).await {
+            eprintln!("Failed to generate bitcoin traffic: {:#}", e);
+        }
+    }
+}
+
+async fn top_up_mempool(
+    wallet: &bitcoind_rpc::Client,
+    config: &config::FeeMarket,
+) -> anyhow::Result<()> {
+    let congestion = congestion().await?.unwrap_or(config.congestion);
+    let target_vsize = u64::from(congestion) * config.block_max_weight / 4;
+
+    let mempool = wallet.get_mempool_info().await?;
+    if mempool.bytes >= target_vsize {
+        return Ok(());
+    }
+
+    let transactions =
+        ((target_vsize - mempool.bytes) / TRANSACTION_VSIZE + 1).min(MAX_TRANSACTIONS_PER_POLL);
+    let address = wallet.get_new_address("bech32").await?;
+
+    // bitcoind executes the requests of a batch in order, each fee rate applies to the
+    // transaction following it
+    let requests = random_fee_rates(
+        usize::try_from(transactions)?,
+        config.min_fee_rate,
+        config.max_fee_rate,
+    )
+    .into_iter()
+    .flat_map(|fee_rate| {
+        vec![
+            Request::set_tx_fee(Amount::from_sat(fee_rate * 1000)),
+            Request::send_to_address(&address, Amount::from_sat(PAYMENT_SATS)),
+        ]
+    })
+    .collect();
+
+    let results = wallet.batch_results::<serde_json::Value>(requests).await?;
+
+    // Some sends fail under heavy congestion because of too long chains of unconfirmed
+    // change, the next poll tops the mempool up again
+    let errors = results
+        .into_iter()
+        .filter_map(Result::err)
+        .collect::<Vec<_>>();
+    if let Some(error) = errors.first() {
+        anyhow::bail!(
+            "{} of {} traffic requests failed, first error: {:#}",
+            errors.len(),
+            transactions * 2,
+            error
+        )
+    }
+
+    Ok(())
+}
+
+/// Sets the number of full blocks worth of transactions kept in the mempool.
+pub async fn set_congestion(level: u32) -> anyhow::Result<()> {
+    super::ensure_running().await?;
+
+    let path = temp_fs::congestion_file_path()?;
+    tokio::fs::write(&path, level.to_string())
+        .await
+        .with_context(|| format!("failed to write file {}", path.display()))?;
+
+    println!("Bitcoin mempool congestion set to {} blocks.", level);
+
+    Ok(())
+}
+
+async fn congestion() -> anyhow::Result<Option<u32>> {
+    let path = temp_fs::congestion_file_path()?;
+
+    match tokio::fs::read_to_string(&path).await {
+        Ok(level) => Ok(level.trim().parse().ok()),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Fee rates in sat/vB, spread over buckets which double in size so that low fee rates are as
+/// common as high ones in relative terms.
+fn random_fee_rates(count: usize, min: u64, max: u64) -> Vec<u64> {
+    let min = min.max(1);
+    let max = max.max(min);
+    let mut rng = thread_rng();
+
+    // [min, 2 * min), [2 * min, 4 * min), ... up to and including max
+    let mut buckets = Vec::new();
+    let mut low = min;
+    while low <= max {
+        let high = low.saturating_mul(2).min(max.saturating_add(1));
+        buckets.push((low, high));
+        low = high;
+        if high > max {
+            break;
+        }
+    }
+
+    (0..count)
+        .map(|_| {
+            let (low, high) = buckets[rng.gen_range(0, buckets.len())];
+            rng.gen_range(low, high)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fee_rates_are_within_bounds() {
+        let fee_rates = random_fee_rates(1000, 2, 50);
+
+        assert_eq!(fee_rates.len(), 1000);
+        assert!(fee_rates.iter().all(|rate| (2..=50).contains(rate)));
+    }
+
+    #[test]
+    fn fee_rates_are_clamped_to_one_sat_per_vbyte() {
+        assert!(random_fee_rates(10, 0, 0).iter().all(|rate| *rate == 1));
+    }
+}
@@ -0,0 +1,82 @@
+//! The variables start-env writes to the env file.
+
+/// Defines a constant per variable and the lists `is_written` checks, so that a new variable
+/// cannot be written without being reserved.
+macro_rules! env_vars {
+    (fixed: [$($name:ident),* $(,)?], per_actor: [$($prefix:ident),* $(,)?] $(,)?) => {
+        $(pub const $name: &str = stringify!($name);)*
+        $(pub const $prefix: &str = stringify!($prefix);)*
+
+        const FIXED: &[&str] = &[$($name),*];
+        /// Written once per actor with the index of the actor as suffix, see `per_actor`.
+        const PER_ACTOR: &[&str] = &[$($prefix),*];
+    };
+}
+
+env_vars! {
+    fixed: [
+        ENVIRONMENT_SEED,
+        ERC20_CONTRACT_ADDRESS,
+        ETHEREUM_NODE_HTTP_URL,
+        ETHEREUM_FUNDING_TXIDS,
+        BITCOIN_FUNDING_TXIDS,
+        BITCOIN_P2P_URI,
+        BITCOIN_HTTP_URI,
+        BITCOIN_HTTP_URI_1,
+        BITCOIN_ZMQ_RAWBLOCK,
+        BITCOIN_ZMQ_RAWTX,
+        BITCOIN_ZMQ_HASHBLOCK,
+        BITCOIN_ZMQ_HASHTX,
+        BITCOIN_USERNAME,
+        BITCOIN_PASSWORD,
+        ELECTRUM_URI,
+        ESPLORA_HTTP_URL,
+        FAUCET_URL,
+    ],
+    per_actor: [
+        ETHEREUM_KEY,
+        MNEMONIC,
+        BITCOIN_WALLET,
+        BITCOIN_WALLET_URL,
+        HTTP_URL_CND,
+        LND_REST_URL,
+        LND_GRPC_URI,
+        LND_PUBKEY,
+        LND_MACAROON,
+        LND_TLS_CERT_PATH,
+    ],
+}
+
+const ACTORS: usize = 2;
+
+/// The variable of one actor, e.g. `MNEMONIC_0`.
+pub fn per_actor(prefix: &str, index: usize) -> String {
+    format!("{}_{}", prefix, index)
+}
+
+/// The variable of the contract address of a configured token, e.g. `ERC20_USDC_CONTRACT_ADDRESS`.
+pub fn token_contract_address(symbol: &str) -> String {
+    format!("ERC20_{}_CONTRACT_ADDRESS", symbol.to_uppercase())
+}
+
+/// Whether start-env writes `name` regardless of the configured tokens and contracts.
+pub fn is_written(name: &str) -> bool {
+    FIXED.contains(&name)
+        || PER_ACTOR
+            .iter()
+            .any(|prefix| (0..ACTORS).any(|index| per_actor(prefix, index) == name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_and_per_actor_variables_are_written() {
+        assert!(is_written("BITCOIN_HTTP_URI"));
+        assert!(is_written("BITCOIN_HTTP_URI_1"));
+        assert!(is_written("LND_PUBKEY_1"));
+        assert!(!is_written("LND_PUBKEY_2"));
+        assert!(!is_written("REGISTRY_ADDRESS"));
+    }
+}
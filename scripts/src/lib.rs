@@ -9,9 +9,12 @@
 )]
 #![forbid(unsafe_code)]
 
+pub mod bitcoind_rpc;
 pub mod config;
 pub mod create_comit_app;
 pub mod docker;
 pub mod env;
+pub mod env_vars;
 pub mod print_progress;
+pub mod seed;
 pub mod temp_fs;
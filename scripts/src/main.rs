@@ -2,9 +2,12 @@
 
 use structopt::StructOpt;
 
-use comit_scripts::{create_comit_app::CreateComitApp, env};
+use comit_scripts::{
+    create_comit_app::{CreateComitApp, Mine, Mining, Network},
+    env,
+};
 
-fn main() -> std::io::Result<()> {
+fn main() -> anyhow::Result<()> {
     let mut runtime = tokio_compat::runtime::Runtime::new()?;
 
     let command = CreateComitApp::from_args();
@@ -14,10 +17,22 @@ fn main() -> std::io::Result<()> {
     Ok(())
 }
 
-async fn run_command(command: CreateComitApp) -> std::io::Result<()> {
+async fn run_command(command: CreateComitApp) -> anyhow::Result<()> {
     match command {
-        CreateComitApp::StartEnv => env::start().await,
+        CreateComitApp::StartEnv { seed } => env::start(seed).await,
         CreateComitApp::ForceCleanEnv => env::clean_up().await,
+        CreateComitApp::Mine(Mine::Btc { blocks, node }) => env::mine_bitcoin(blocks, node).await?,
+        CreateComitApp::Mining(Mining::Pause) => env::pause_mining().await?,
+        CreateComitApp::Mining(Mining::Resume) => env::resume_mining().await?,
+        CreateComitApp::Network(Network::Disconnect) => env::disconnect_nodes().await?,
+        CreateComitApp::Network(Network::Connect) => env::connect_nodes().await?,
+        CreateComitApp::Reorg { depth } => env::reorg(depth).await?,
+        CreateComitApp::InvalidateBlocks { blocks, remine } => {
+            env::invalidate_blocks(blocks, remine).await?
+        }
+        CreateComitApp::ReconsiderBlocks => env::reconsider_blocks().await?,
+        CreateComitApp::Congestion { level } => env::set_congestion(level).await?,
+        CreateComitApp::AdvanceTime { duration } => env::advance_time(&duration).await?,
     }
 
     Ok(())
@@ -0,0 +1,66 @@
+use std::fmt;
+
+use bip39::{Language, Mnemonic};
+use rust_bitcoin::hashes::{sha256, Hash};
+use secp256k1::rand::{thread_rng, Rng};
+
+/// The seed all keys of an environment are derived from.
+///
+/// Starting an environment twice with the same seed yields the same keys and contract addresses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Seed(String);
+
+impl Seed {
+    pub fn new(seed: String) -> Self {
+        Self(seed)
+    }
+
+    pub fn random() -> Self {
+        let mut bytes = [0u8; 32];
+        thread_rng().fill(&mut bytes);
+
+        Self(hex::encode(bytes))
+    }
+
+    /// Derives 32 bytes for the given purpose, e.g. `bitcoin/0` for the first Bitcoin account.
+    pub fn derive(&self, label: &str) -> [u8; 32] {
+        sha256::Hash::hash(format!("{}/{}", self.0, label).as_bytes()).into_inner()
+    }
+
+    /// The 12 word BIP39 mnemonic all keys of the actor with the given index are derived from.
+    pub fn mnemonic(&self, index: u32) -> anyhow::Result<Mnemonic> {
+        let entropy = self.derive(&format!("mnemonic/{}", index));
+
+        Mnemonic::from_entropy(&entropy[..16], Language::English)
+            .map_err(|e| anyhow::anyhow!("failed to create mnemonic from entropy {:?}", e))
+    }
+}
+
+impl fmt::Display for Seed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_derives_same_bytes() {
+        let seed = Seed::new("42".to_owned());
+
+        assert_eq!(
+            seed.derive("bitcoin/0"),
+            Seed::new("42".to_owned()).derive("bitcoin/0")
+        );
+        assert_ne!(seed.derive("bitcoin/0"), seed.derive("bitcoin/1"));
+    }
+
+    #[test]
+    fn mnemonic_has_12_words() {
+        let mnemonic = Seed::new("42".to_owned()).mnemonic(0).unwrap();
+
+        assert_eq!(mnemonic.phrase().split_whitespace().count(), 12);
+    }
+}
@@ -4,6 +4,9 @@ use anyhow::Context;
 
 pub const DIR_NAME: &str = ".create-comit-app";
 const ENV_FILE_NAME: &str = "env";
+const MINING_PAUSED_FILE_NAME: &str = "mining_paused";
+const CONGESTION_FILE_NAME: &str = "congestion";
+const TIME_OFFSET_FILE_NAME: &str = "time_offset";
 
 fn home() -> anyhow::Result<PathBuf> {
     dirs::home_dir().ok_or_else(|| anyhow::anyhow!("unable to determine home directory"))
@@ -17,6 +20,21 @@ pub fn env_file_path() -> anyhow::Result<PathBuf> {
     Ok(dir_path()?.join(ENV_FILE_NAME))
 }
 
+/// The miner of a running environment does not mine while this file exists.
+pub fn mining_paused_file_path() -> anyhow::Result<PathBuf> {
+    Ok(dir_path()?.join(MINING_PAUSED_FILE_NAME))
+}
+
+/// Overrides the configured congestion of the fee market of a running environment.
+pub fn congestion_file_path() -> anyhow::Result<PathBuf> {
+    Ok(dir_path()?.join(CONGESTION_FILE_NAME))
+}
+
+/// Seconds the clocks of the running environment are ahead of the system clock.
+pub fn time_offset_file_path() -> anyhow::Result<PathBuf> {
+    Ok(dir_path()?.join(TIME_OFFSET_FILE_NAME))
+}
+
 pub async fn create_env_file() -> anyhow::Result<String> {
     let _ = ensure_cca_directory().await?;
 